@@ -0,0 +1,351 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+
+use crate::{
+    hex::Hex,
+    opcode::{opcodes, push_width, OpCode, OpCodes},
+};
+
+// Errors in assembly text, with the line they were found on, counting from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssemblyError {
+    UnknownMnemonic { line: usize, mnemonic: String },
+    // The instruction only exists in EOF code, which needs a container this does not produce.
+    EofOnly { line: usize, mnemonic: String },
+    InvalidOperand { line: usize, operand: String },
+    MissingOperand { line: usize },
+    UnexpectedOperand { line: usize },
+    // The operand does not fit in the immediate of an explicitly sized PUSH.
+    OperandTooLarge { line: usize, width: usize },
+    UnknownLabel { line: usize, label: String },
+    DuplicateLabel { line: usize, label: String },
+}
+
+impl Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssemblyError::UnknownMnemonic { line, mnemonic } => {
+                write!(f, "line {}: unknown mnemonic {}", line, mnemonic)
+            }
+            AssemblyError::EofOnly { line, mnemonic } => {
+                write!(f, "line {}: {} is only valid in EOF code", line, mnemonic)
+            }
+            AssemblyError::InvalidOperand { line, operand } => {
+                write!(f, "line {}: invalid operand {}", line, operand)
+            }
+            AssemblyError::MissingOperand { line } => {
+                write!(f, "line {}: PUSH needs an operand", line)
+            }
+            AssemblyError::UnexpectedOperand { line } => {
+                write!(f, "line {}: only PUSH takes an operand", line)
+            }
+            AssemblyError::OperandTooLarge { line, width } => {
+                write!(f, "line {}: operand does not fit in {} bytes", line, width)
+            }
+            AssemblyError::UnknownLabel { line, label } => {
+                write!(f, "line {}: unknown label {}", line, label)
+            }
+            AssemblyError::DuplicateLabel { line, label } => {
+                write!(f, "line {}: label {} is already defined", line, label)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssemblyError {}
+
+enum Operand {
+    Value(Hex),
+    Label(String),
+}
+
+struct Statement {
+    line: usize,
+    opcode: OpCodes,
+    // Immediate size of a PUSH, or None for a PUSH without a size, which gets the smallest
+    // size that fits its operand.
+    width: Option<usize>,
+    operand: Option<Operand>,
+}
+
+fn parse_operand(operand: &str, line: usize) -> Result<Operand, AssemblyError> {
+    let invalid = || AssemblyError::InvalidOperand {
+        line,
+        operand: operand.to_string(),
+    };
+    if let Some(hex) = operand.strip_prefix("0x") {
+        Hex::from_str_radix(hex, 16)
+            .map(Operand::Value)
+            .map_err(|_| invalid())
+    } else if operand.starts_with(|c: char| c.is_ascii_digit()) {
+        Hex::from_str_radix(operand, 10)
+            .map(Operand::Value)
+            .map_err(|_| invalid())
+    } else if operand
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        Ok(Operand::Label(operand.to_string()))
+    } else {
+        Err(invalid())
+    }
+}
+
+// Turns assembly text into bytecode. Every line holds an instruction, a label definition like
+// `loop:`, or both, and `;` starts a comment:
+//
+//     PUSH loop      ; PUSH without a size fits its operand
+//     JUMP
+//     loop:
+//     JUMPDEST
+//     PUSH1 0x80
+//
+// Labels stand for the offset of the instruction following them, so they normally precede a
+// JUMPDEST. A PUSH of a label gets the smallest size that fits the resolved offset.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssemblyError> {
+    let mut mnemonics: HashMap<String, OpCode> = opcodes()
+        .into_values()
+        .map(|opcode| (opcode.short_name.clone(), opcode))
+        .collect();
+    mnemonics.insert("KECCAK256".to_string(), mnemonics["SHA3"].clone());
+    mnemonics.insert("PREVRANDAO".to_string(), mnemonics["DIFFICULTY"].clone());
+
+    // Labels point at the index of the statement following them.
+    let mut labels: HashMap<String, usize> = HashMap::new();
+    let mut statements: Vec<Statement> = Vec::new();
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let mut text = text.split(';').next().unwrap_or_default().trim();
+        if let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if labels.insert(label.to_string(), statements.len()).is_some() {
+                return Err(AssemblyError::DuplicateLabel {
+                    line,
+                    label: label.to_string(),
+                });
+            }
+            text = rest.trim();
+        }
+        let mut words = text.split_whitespace();
+        let Some(mnemonic) = words.next() else {
+            continue;
+        };
+        let mnemonic = mnemonic.to_uppercase();
+        let operand = words
+            .next()
+            .map(|operand| parse_operand(operand, line))
+            .transpose()?;
+        if let Some(extra) = words.next() {
+            return Err(AssemblyError::InvalidOperand {
+                line,
+                operand: extra.to_string(),
+            });
+        }
+        let (opcode, width) = if mnemonic == "PUSH" {
+            (OpCodes::PUSH1, None)
+        } else {
+            let Some(opcode) = mnemonics.get(&mnemonic) else {
+                return Err(AssemblyError::UnknownMnemonic { line, mnemonic });
+            };
+            if opcode.eof_only {
+                return Err(AssemblyError::EofOnly { line, mnemonic });
+            }
+            (opcode.code.clone(), push_width(&opcode.code))
+        };
+        let takes_operand = width.map_or(opcode == OpCodes::PUSH1, |width| width > 0);
+        match (takes_operand, &operand) {
+            (true, None) => return Err(AssemblyError::MissingOperand { line }),
+            (false, Some(_)) => return Err(AssemblyError::UnexpectedOperand { line }),
+            _ => {}
+        }
+        statements.push(Statement {
+            line,
+            opcode,
+            width,
+            operand,
+        });
+    }
+
+    let resolve = |statement: &Statement, label: &str| {
+        labels
+            .get(label)
+            .copied()
+            .ok_or(AssemblyError::UnknownLabel {
+                line: statement.line,
+                label: label.to_string(),
+            })
+    };
+
+    // Label offsets depend on the sizes of the pushes before them, which in turn depend on the
+    // label offsets. Start from the smallest sizes and grow them until every label fits; sizes
+    // only grow, so this ends.
+    let mut widths: Vec<usize> = statements
+        .iter()
+        .map(|statement| match (statement.width, &statement.operand) {
+            (Some(width), _) => width,
+            (None, Some(Operand::Value(value))) => value.byte_len().max(1),
+            (None, Some(Operand::Label(_))) => 1,
+            (None, None) => 0,
+        })
+        .collect();
+    let offsets = loop {
+        let mut offsets = Vec::with_capacity(statements.len() + 1);
+        let mut offset = 0;
+        for width in &widths {
+            offsets.push(offset);
+            offset += 1 + width;
+        }
+        offsets.push(offset);
+
+        let mut grown = false;
+        for (statement, width) in statements.iter().zip(widths.iter_mut()) {
+            if let (None, Some(Operand::Label(label))) = (statement.width, &statement.operand) {
+                let target = offsets[resolve(statement, label)?];
+                let needed = Hex::from(target).byte_len().max(1);
+                if needed > *width {
+                    *width = needed;
+                    grown = true;
+                }
+            }
+        }
+        if !grown {
+            break offsets;
+        }
+    };
+
+    let mut bytecode = Vec::new();
+    for (statement, width) in statements.iter().zip(&widths) {
+        let value = match &statement.operand {
+            Some(Operand::Value(value)) => *value,
+            Some(Operand::Label(label)) => Hex::from(offsets[resolve(statement, label)?]),
+            None => Hex::ZERO,
+        };
+        if value.byte_len() > *width {
+            return Err(AssemblyError::OperandTooLarge {
+                line: statement.line,
+                width: *width,
+            });
+        }
+        if statement.operand.is_some() || statement.width.is_some() {
+            bytecode.push(OpCodes::PUSH0 as u8 + *width as u8);
+            bytecode.extend_from_slice(&value.to_be_bytes()[32 - width..]);
+        } else {
+            bytecode.push(statement.opcode.clone() as u8);
+        }
+    }
+    Ok(bytecode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assemble, AssemblyError};
+
+    #[test]
+    fn instructions() {
+        let bytecode = assemble(
+            "
+            PUSH1 0x80
+            PUSH1 64   ; decimal
+            mstore
+            PUSH0
+            PUSH2 0x1
+            KECCAK256
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            bytecode,
+            [0x60, 0x80, 0x60, 0x40, 0x52, 0x5f, 0x61, 0x00, 0x01, 0x20]
+        );
+    }
+
+    #[test]
+    fn push_without_size_fits_operand() {
+        let bytecode = assemble("PUSH 0\nPUSH 0x1234\nPUSH 256").unwrap();
+        assert_eq!(bytecode, [0x60, 0x00, 0x61, 0x12, 0x34, 0x61, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn labels() {
+        let bytecode = assemble(
+            "
+            PUSH end
+            JUMP
+            loop: JUMPDEST
+            PUSH loop
+            JUMP
+            end:
+            JUMPDEST
+            STOP
+            ",
+        )
+        .unwrap();
+        assert_eq!(
+            bytecode,
+            [0x60, 0x07, 0x56, 0x5b, 0x60, 0x03, 0x56, 0x5b, 0x00]
+        );
+    }
+
+    #[test]
+    fn labels_grow_to_fit() {
+        // 128 PUSH1 instructions push the label past 0xff, so its PUSH needs two bytes, which
+        // moves the label one byte further.
+        let source = format!("PUSH end\n{}end: JUMPDEST\n", "PUSH1 0x00\n".repeat(128));
+        let bytecode = assemble(&source).unwrap();
+        assert_eq!(bytecode[0..3], [0x61, 0x01, 0x03]);
+        assert_eq!(bytecode[0x103], 0x5b);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            assemble("PUSH1 0x80\nFOO"),
+            Err(AssemblyError::UnknownMnemonic {
+                line: 2,
+                mnemonic: "FOO".to_string()
+            })
+        );
+        assert_eq!(
+            assemble("STOP\nRJUMP 0x0003"),
+            Err(AssemblyError::EofOnly {
+                line: 2,
+                mnemonic: "RJUMP".to_string()
+            })
+        );
+        assert_eq!(
+            assemble("CALLF"),
+            Err(AssemblyError::EofOnly {
+                line: 1,
+                mnemonic: "CALLF".to_string()
+            })
+        );
+        assert_eq!(
+            assemble("PUSH1"),
+            Err(AssemblyError::MissingOperand { line: 1 })
+        );
+        assert_eq!(
+            assemble("ADD 0x1"),
+            Err(AssemblyError::UnexpectedOperand { line: 1 })
+        );
+        assert_eq!(
+            assemble("PUSH1 0x100"),
+            Err(AssemblyError::OperandTooLarge { line: 1, width: 1 })
+        );
+        assert_eq!(
+            assemble("PUSH nowhere"),
+            Err(AssemblyError::UnknownLabel {
+                line: 1,
+                label: "nowhere".to_string()
+            })
+        );
+        assert_eq!(
+            assemble("a: JUMPDEST\na: JUMPDEST"),
+            Err(AssemblyError::DuplicateLabel {
+                line: 2,
+                label: "a".to_string()
+            })
+        );
+    }
+}
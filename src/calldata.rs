@@ -1,42 +1,90 @@
 use std::io::{Error, ErrorKind};
 
-use crate::{hex::Hex, stack::StackElement};
+use crate::hex::Hex;
 
+// The input of a transaction. Reads past the end of the calldata return zeros, like they do in
+// the EVM.
+#[derive(Debug, Clone, Default)]
 pub struct CallData {
-    value: String,
+    bytes: Vec<u8>,
 }
 
 impl CallData {
-    pub fn new(value: String) -> CallData {
-        CallData { value }
+    pub fn new(bytes: Vec<u8>) -> CallData {
+        CallData { bytes }
     }
 
-    pub fn get(&self, offset: usize) -> Result<StackElement, std::io::Error> {
-        println!("CALLDATA: {}", self.value);
-        println!("len: {}", self.value.len());
-        println!("offset: {}", offset);
-        let substr = self.value.get(offset..offset + 32).unwrap().to_string();
-        println!("substr: {}", substr);
-        let value: Hex = match Hex::try_from(&substr) {
-            Ok(v) => v,
-            Err(e) => return Err(Error::new(ErrorKind::InvalidInput, e)),
-        };
-        println!("value: {}", value);
-        let value = StackElement {
-            value,
-            origin: Hex(0),
-            //size: (format!("{:x}", value.0).len() + 1) / 2 as usize,
-            size: 32,
-        };
-        println!("Calldata: {:?}", value);
-        Ok(value)
+    // Parses calldata from a hexadecimal string, with or without a leading `0x`.
+    pub fn from_hex(value: &str) -> Result<CallData, std::io::Error> {
+        let value = value.trim_start_matches("0x");
+        if !value.len().is_multiple_of(2) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Calldata {} has an odd number of digits", value),
+            ));
+        }
+        let bytes = (0..value.len())
+            .step_by(2)
+            .map(|i| {
+                value
+                    .get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Calldata {} is not hexadecimal", value),
+                    ))
+            })
+            .collect::<Result<Vec<u8>, Error>>()?;
+        Ok(CallData::new(bytes))
+    }
+
+    // Returns the byte at `offset`, or zero when reading past the end of the calldata.
+    pub fn byte(&self, offset: Hex) -> u8 {
+        usize::try_from(offset)
+            .ok()
+            .and_then(|offset| self.bytes.get(offset).copied())
+            .unwrap_or(0)
     }
-    pub fn size(&self) -> StackElement {
-        let size = self.value.len() / 2;
-        StackElement {
-            value: size.into(),
-            origin: Hex(0),
-            size: 1,
+
+    // Returns the 32 byte word starting at `offset`, padded with zeros on the right when it
+    // runs past the end of the calldata.
+    pub fn get(&self, offset: Hex) -> Hex {
+        let mut word = [0u8; 32];
+        for (i, byte) in word.iter_mut().enumerate() {
+            let (position, overflow) = offset.overflowing_add(Hex::from(i));
+            if !overflow {
+                *byte = self.byte(position);
+            }
         }
+        Hex::from_be_bytes(&word)
+    }
+
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex::Hex;
+
+    use super::CallData;
+
+    #[test]
+    fn from_hex() {
+        let calldata = CallData::from_hex("0xa9059cbb").unwrap();
+        assert_eq!(calldata.size(), 4);
+        assert_eq!(calldata.byte(Hex::new(1)), 0x05);
+        assert!(CallData::from_hex("a9059cb").is_err());
+        assert!(CallData::from_hex("a9059cbx").is_err());
+    }
+
+    #[test]
+    fn get_pads_with_zeros() {
+        let calldata = CallData::from_hex("a9059cbb").unwrap();
+        assert_eq!(calldata.get(Hex::new(0)), Hex::new(0xa9059cbb) << 224);
+        assert_eq!(calldata.get(Hex::new(2)), Hex::new(0x9cbb) << 240);
+        assert_eq!(calldata.get(Hex::new(4)), Hex::new(0));
+        assert_eq!(calldata.get(Hex::MAX), Hex::new(0));
     }
 }
@@ -1,7 +1,359 @@
-struct CBor {}
+// Compilers append metadata to the code they generate, encoded as CBOR and followed by its
+// length as a 2 byte big endian number. Solidity encodes a map such as
+//
+//     {"ipfs": h'1220…', "solc": h'000813'}
+//
+// and Vyper, from 0.3.10 on, an array of the sizes of the parts of the code followed by a map
+// with the compiler version, with a length that includes the 2 length bytes themselves.
 
-impl CBor {
-    pub fn new -> CBor {
-        CBor{}
+use std::fmt;
+
+// The metadata found at the end of bytecode.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    // The offset the metadata starts at, which is where the code ends.
+    pub start: usize,
+    // The hash of the metadata file on IPFS.
+    pub ipfs: Option<Vec<u8>>,
+    // The hash of the metadata file on Swarm, in the two versions of the Swarm hash.
+    pub bzzr0: Option<Vec<u8>>,
+    pub bzzr1: Option<Vec<u8>>,
+    // The solc version, like "0.8.19" for releases, or the full version string for others.
+    pub solc: Option<String>,
+    // Whether experimental compiler features were used.
+    pub experimental: bool,
+    pub vyper: Option<VyperMetadata>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VyperMetadata {
+    pub version: String,
+    // The sizes of the parts of the code, which Vyper only records from 0.3.10 on.
+    pub runtime_size: Option<u64>,
+    pub data_section_sizes: Vec<u64>,
+    pub immutable_size: Option<u64>,
+}
+
+// Lists the metadata as comma separated parts, like `solc 0.8.19, ipfs 0x1220…`.
+impl fmt::Display for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = |bytes: &Vec<u8>| -> String {
+            bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+        };
+        let mut parts = Vec::new();
+        if let Some(solc) = &self.solc {
+            parts.push(format!("solc {}", solc));
+        }
+        if let Some(vyper) = &self.vyper {
+            parts.push(format!("vyper {}", vyper.version));
+            if let Some(runtime_size) = vyper.runtime_size {
+                parts.push(format!("runtime size {:#x}", runtime_size));
+            }
+            for size in &vyper.data_section_sizes {
+                parts.push(format!("data section size {:#x}", size));
+            }
+            if let Some(immutable_size) = vyper.immutable_size {
+                parts.push(format!("immutable size {:#x}", immutable_size));
+            }
+        }
+        for (name, hash) in [
+            ("ipfs", &self.ipfs),
+            ("bzzr0", &self.bzzr0),
+            ("bzzr1", &self.bzzr1),
+        ] {
+            if let Some(hash) = hash {
+                parts.push(format!("{} 0x{}", name, hex(hash)));
+            }
+        }
+        if self.experimental {
+            parts.push("experimental".to_string());
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+// The subset of CBOR that compilers use for their metadata.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Unsigned(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Bool(bool),
+    Null,
+}
+
+// Metadata nests an array in a map in an array at most, so anything deeper is not metadata.
+// Without a limit, deeply nested items in arbitrary code exhaust the stack.
+const MAX_DEPTH: usize = 4;
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Decoder<'_> {
+    fn take(&mut self, length: usize) -> Option<&[u8]> {
+        let end = self.position.checked_add(length)?;
+        let bytes = self.bytes.get(self.position..end)?;
+        self.position = end;
+        Some(bytes)
+    }
+
+    // Reads the argument of a data item, which is either in the additional information of its
+    // first byte or in the 1, 2, 4 or 8 bytes after it.
+    fn argument(&mut self, additional: u8) -> Option<u64> {
+        let length = match additional {
+            0..=23 => return Some(additional as u64),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            // Indefinite lengths are not used in metadata.
+            _ => return None,
+        };
+        Some(
+            self.take(length)?
+                .iter()
+                .fold(0, |value, byte| (value << 8) | *byte as u64),
+        )
+    }
+
+    // Reads a data item that is nested in `depth` arrays and maps.
+    fn value(&mut self, depth: usize) -> Option<Value> {
+        let first = *self.take(1)?.first()?;
+        let (major, additional) = (first >> 5, first & 0x1f);
+        if major == 7 {
+            return match additional {
+                20 => Some(Value::Bool(false)),
+                21 => Some(Value::Bool(true)),
+                22 => Some(Value::Null),
+                _ => None,
+            };
+        }
+        let argument = self.argument(additional)?;
+        match major {
+            0 => Some(Value::Unsigned(argument)),
+            2 => Some(Value::Bytes(self.take(argument as usize)?.to_vec())),
+            3 => String::from_utf8(self.take(argument as usize)?.to_vec())
+                .ok()
+                .map(Value::Text),
+            // Every item takes at least a byte, which bounds the number of items.
+            4 if depth < MAX_DEPTH && argument as usize <= self.bytes.len() => (0..argument)
+                .map(|_| self.value(depth + 1))
+                .collect::<Option<_>>()
+                .map(Value::Array),
+            5 if depth < MAX_DEPTH && argument as usize <= self.bytes.len() => (0..argument)
+                .map(|_| Some((self.value(depth + 1)?, self.value(depth + 1)?)))
+                .collect::<Option<_>>()
+                .map(Value::Map),
+            _ => None,
+        }
+    }
+}
+
+// Decodes `bytes` as a single CBOR value, or returns None when they are not exactly that.
+fn decode(bytes: &[u8]) -> Option<Value> {
+    let mut decoder = Decoder { bytes, position: 0 };
+    let value = decoder.value(0)?;
+    (decoder.position == bytes.len()).then_some(value)
+}
+
+fn version(value: &Value) -> Option<String> {
+    match value {
+        Value::Bytes(bytes) if bytes.len() == 3 => {
+            Some(format!("{}.{}.{}", bytes[0], bytes[1], bytes[2]))
+        }
+        Value::Array(parts) => parts
+            .iter()
+            .map(|part| match part {
+                Value::Unsigned(part) => Some(part.to_string()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|parts| parts.join(".")),
+        Value::Text(text) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+fn unsigned(value: &Value) -> Option<u64> {
+    match value {
+        Value::Unsigned(value) => Some(*value),
+        _ => None,
+    }
+}
+
+impl Metadata {
+    // Finds the metadata at the end of `bytecode`, or returns None when it does not end with
+    // metadata that is understood.
+    pub fn from_bytecode(bytecode: &[u8]) -> Option<Metadata> {
+        let suffix = bytecode.len().checked_sub(2)?;
+        let length = u16::from_be_bytes([bytecode[suffix], bytecode[suffix + 1]]) as usize;
+        // Solidity and older Vyper versions leave the length bytes out of the length, newer
+        // Vyper versions count them.
+        [length, length.wrapping_sub(2)]
+            .into_iter()
+            .filter_map(|length| suffix.checked_sub(length))
+            .find_map(|start| {
+                let mut metadata = Metadata::from_value(decode(&bytecode[start..suffix])?)?;
+                metadata.start = start;
+                Some(metadata)
+            })
+    }
+
+    fn from_value(value: Value) -> Option<Metadata> {
+        let (sizes, entries) = match value {
+            Value::Map(entries) => (Vec::new(), entries),
+            Value::Array(mut items) => match items.pop() {
+                Some(Value::Map(entries)) => (items, entries),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let mut metadata = Metadata::default();
+        for (key, value) in &entries {
+            match (key, value) {
+                (Value::Text(key), Value::Bytes(hash)) if key == "ipfs" => {
+                    metadata.ipfs = Some(hash.clone())
+                }
+                (Value::Text(key), Value::Bytes(hash)) if key == "bzzr0" => {
+                    metadata.bzzr0 = Some(hash.clone())
+                }
+                (Value::Text(key), Value::Bytes(hash)) if key == "bzzr1" => {
+                    metadata.bzzr1 = Some(hash.clone())
+                }
+                (Value::Text(key), Value::Bool(experimental)) if key == "experimental" => {
+                    metadata.experimental = *experimental
+                }
+                (Value::Text(key), value) if key == "solc" => metadata.solc = Some(version(value)?),
+                (Value::Text(key), value) if key == "vyper" => {
+                    metadata.vyper = Some(VyperMetadata {
+                        version: version(value)?,
+                        ..Default::default()
+                    })
+                }
+                // Anything else is not compiler metadata, but code that happens to end in
+                // something that decodes.
+                _ => return None,
+            }
+        }
+        if entries.is_empty() {
+            return None;
+        }
+        if !sizes.is_empty() {
+            // [runtime size, [data section sizes], immutable size, {"vyper": version}]
+            let vyper = metadata.vyper.as_mut()?;
+            let [runtime_size, Value::Array(data_section_sizes), immutable_size] = &sizes[..]
+            else {
+                return None;
+            };
+            vyper.runtime_size = Some(unsigned(runtime_size)?);
+            vyper.data_section_sizes = data_section_sizes
+                .iter()
+                .map(unsigned)
+                .collect::<Option<_>>()?;
+            vyper.immutable_size = Some(unsigned(immutable_size)?);
+        }
+        Some(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Metadata, VyperMetadata};
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn solidity_ipfs() {
+        let bytecode = from_hex(
+            "5056fea26469706673582212206471fae051fe349afbc0803628e924a4d57b36e4067d38265220429afd\
+             34242d64736f6c63430008130033",
+        );
+        let metadata = Metadata::from_bytecode(&bytecode).unwrap();
+        assert_eq!(metadata.start, 3);
+        assert_eq!(metadata.solc.as_deref(), Some("0.8.19"));
+        let ipfs = metadata.ipfs.clone().unwrap();
+        assert_eq!(ipfs.len(), 34);
+        assert_eq!(ipfs[..2], [0x12, 0x20]);
+        assert!(!metadata.experimental);
+        assert_eq!(metadata.bzzr0, None);
+        assert_eq!(metadata.to_string().split(", ").next(), Some("solc 0.8.19"));
+    }
+
+    #[test]
+    fn solidity_swarm_and_experimental() {
+        // {"bzzr0": h'00…', "experimental": true}
+        let bytecode = from_hex(&format!(
+            "00a265627a7a72305820{}6c6578706572696d656e74616cf50037",
+            "00".repeat(32)
+        ));
+        let metadata = Metadata::from_bytecode(&bytecode).unwrap();
+        assert_eq!(metadata.start, 1);
+        assert_eq!(metadata.bzzr0, Some(vec![0; 32]));
+        assert!(metadata.experimental);
+        assert_eq!(metadata.solc, None);
+
+        // {"bzzr1": h'00…', "solc": "0.5.17-nightly"}
+        let bytecode = from_hex(&format!(
+            "a265627a7a72315820{}64736f6c636e302e352e31372d6e696768746c79003d",
+            "00".repeat(32)
+        ));
+        let metadata = Metadata::from_bytecode(&bytecode).unwrap();
+        assert_eq!(metadata.bzzr1, Some(vec![0; 32]));
+        assert_eq!(metadata.solc.as_deref(), Some("0.5.17-nightly"));
+    }
+
+    #[test]
+    fn vyper() {
+        // {"vyper": [0, 3, 4]}
+        let bytecode = from_hex("00a165767970657283000304000b");
+        let metadata = Metadata::from_bytecode(&bytecode).unwrap();
+        assert_eq!(metadata.start, 1);
+        assert_eq!(metadata.vyper.unwrap().version, "0.3.4");
+
+        // [0x1234, [], 0x20, {"vyper": [0, 3, 10]}], with the length bytes counted.
+        let bytecode = from_hex("0084191234801820a16576797065728300030a0014");
+        let metadata = Metadata::from_bytecode(&bytecode).unwrap();
+        assert_eq!(metadata.start, 1);
+        assert_eq!(
+            metadata.vyper,
+            Some(VyperMetadata {
+                version: "0.3.10".to_string(),
+                runtime_size: Some(0x1234),
+                data_section_sizes: Vec::new(),
+                immutable_size: Some(0x20),
+            })
+        );
+    }
+
+    #[test]
+    fn deeply_nested() {
+        // 64999 arrays of one item nested in each other around a 0, followed by their length.
+        let mut bytecode = vec![0x81; 64999];
+        bytecode.extend([0x00, 0xfd, 0xe8]);
+        assert_eq!(Metadata::from_bytecode(&bytecode), None);
+    }
+
+    #[test]
+    fn no_metadata() {
+        assert_eq!(Metadata::from_bytecode(&[]), None);
+        assert_eq!(Metadata::from_bytecode(&[0x00]), None);
+        // A length pointing before the start of the code.
+        assert_eq!(Metadata::from_bytecode(&from_hex("6080604052ff00")), None);
+        // Valid CBOR, but not metadata.
+        assert_eq!(Metadata::from_bytecode(&from_hex("a16161010004")), None);
+        // Truncated metadata.
+        assert_eq!(
+            Metadata::from_bytecode(&from_hex("a1657679706572830003000b")),
+            None
+        );
     }
 }
@@ -0,0 +1,416 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::{
+    flow::Flow,
+    hex::Hex,
+    instruction::{Instruction, JumpType},
+    opcode::{push_width, OpCodes},
+    parser::Parser,
+};
+
+// A run of instructions that is only entered at its first instruction and only left after its
+// last one. Blocks start at JUMPDESTs and jump targets, and end after jumps and halts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub start: Hex,
+    // The offset of the last instruction.
+    pub end: Hex,
+    pub instructions: Vec<Hex>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EdgeKind {
+    // Execution runs into the next block, which starts with a JUMPDEST.
+    Fallthrough,
+    // A conditional jump is taken, or for RJUMPV one of its cases.
+    ConditionalTrue,
+    // A conditional jump is not taken and continues after the jump.
+    ConditionalFalse,
+    Unconditional,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Edge {
+    pub from: Hex,
+    pub to: Hex,
+    pub kind: EdgeKind,
+}
+
+// The control-flow graph of the code of a parser. Jumps are resolved statically where the code
+// says where they go: relative jumps, and jumps right after the PUSH of their target. The other
+// targets are only known from executing the code, which `add_flows` adds.
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    // The blocks execution starts at: the start of the code, or for EOF every code section,
+    // since the other sections are entered through CALLF.
+    entries: Vec<Hex>,
+    blocks: BTreeMap<Hex, BasicBlock>,
+    edges: BTreeSet<Edge>,
+    // The blocks that end in a jump of which no target is known.
+    unresolved: BTreeSet<Hex>,
+}
+
+impl Cfg {
+    pub fn new(parser: &Parser) -> Cfg {
+        let instructions: BTreeMap<Hex, &Instruction> = parser
+            .get_instructions()
+            .iter()
+            .map(|(offset, instruction)| (*offset, instruction))
+            .collect();
+        let sections: Vec<Hex> = match parser.get_container() {
+            Some(container) => container
+                .code_sections
+                .iter()
+                .map(|section| Hex::from(section.offset))
+                .collect(),
+            None => vec![Hex::ZERO],
+        };
+
+        let mut leaders: BTreeSet<Hex> = sections.iter().copied().collect();
+        for instruction in instructions.values() {
+            if instruction.opcode.code == OpCodes::JUMPDEST {
+                leaders.insert(instruction.index);
+            }
+            if ends_block(instruction) {
+                leaders.insert(next_offset(instruction));
+            }
+            leaders.extend(instruction.relative_targets());
+        }
+
+        let mut blocks: BTreeMap<Hex, BasicBlock> = BTreeMap::new();
+        let mut current: Option<BasicBlock> = None;
+        for (offset, instruction) in &instructions {
+            match current.as_mut() {
+                Some(block) if !leaders.contains(offset) => {
+                    block.end = *offset;
+                    block.instructions.push(*offset);
+                }
+                _ => {
+                    if let Some(block) = current.take() {
+                        blocks.insert(block.start, block);
+                    }
+                    current = Some(BasicBlock {
+                        start: *offset,
+                        end: *offset,
+                        instructions: vec![*offset],
+                    });
+                }
+            }
+            if ends_block(instruction) {
+                if let Some(block) = current.take() {
+                    blocks.insert(block.start, block);
+                }
+            }
+        }
+        if let Some(block) = current {
+            blocks.insert(block.start, block);
+        }
+
+        let mut cfg = Cfg {
+            entries: sections.clone(),
+            blocks,
+            edges: BTreeSet::new(),
+            unresolved: BTreeSet::new(),
+        };
+        let blocks: Vec<BasicBlock> = cfg.blocks.values().cloned().collect();
+        for block in blocks {
+            let last = instructions[&block.end];
+            let next = next_offset(last);
+            match last.opcode.code {
+                OpCodes::JUMP | OpCodes::JUMPI => {
+                    let conditional = last.opcode.code == OpCodes::JUMPI;
+                    let kind = if conditional {
+                        EdgeKind::ConditionalTrue
+                    } else {
+                        EdgeKind::Unconditional
+                    };
+                    let target = block
+                        .instructions
+                        .iter()
+                        .rev()
+                        .nth(1)
+                        .map(|offset| instructions[offset])
+                        .filter(|push| push_width(&push.opcode.code).is_some())
+                        .map(|push| push.push_value());
+                    match target.filter(|target| {
+                        instructions
+                            .get(target)
+                            .is_some_and(|jumpdest| jumpdest.opcode.code == OpCodes::JUMPDEST)
+                    }) {
+                        Some(target) => cfg.add_edge(block.start, target, kind),
+                        None => {
+                            cfg.unresolved.insert(block.start);
+                        }
+                    }
+                    if conditional {
+                        cfg.add_edge(block.start, next, EdgeKind::ConditionalFalse);
+                    }
+                }
+                OpCodes::RJUMP => {
+                    for target in last.relative_targets() {
+                        cfg.add_edge(block.start, target, EdgeKind::Unconditional);
+                    }
+                }
+                OpCodes::RJUMPI | OpCodes::RJUMPV => {
+                    for target in last.relative_targets() {
+                        cfg.add_edge(block.start, target, EdgeKind::ConditionalTrue);
+                    }
+                    cfg.add_edge(block.start, next, EdgeKind::ConditionalFalse);
+                }
+                OpCodes::JUMPF => {
+                    let section = last
+                        .args
+                        .iter()
+                        .fold(0, |value, arg| (value << 8) + arg.as_usize());
+                    if let Some(target) = sections.get(section) {
+                        cfg.add_edge(block.start, *target, EdgeKind::Unconditional);
+                    }
+                }
+                _ if last.opcode.halts => {}
+                _ => cfg.add_edge(block.start, next, EdgeKind::Fallthrough),
+            }
+        }
+        cfg
+    }
+
+    // Adds the jumps taken in `flows` of the code of `parser`, which resolves jumps whose target
+    // is computed. Relative jumps are already known, and jumps to a symbolic target or to
+    // anything but a JUMPDEST halt, so they stay unresolved.
+    pub fn add_flows(&mut self, parser: &Parser, flows: &[Flow]) {
+        for jump in flows.iter().flat_map(|flow| flow.jumps()) {
+            if jump.instruction.opcode.eof_only
+                || jump.destination(parser.get_instructions()).is_none()
+            {
+                continue;
+            }
+            // The source of a jump is where its instruction set starts, which can be an earlier
+            // block when the set runs through a JUMPI.
+            let Some(from) = self
+                .block_containing(jump.instruction.index)
+                .map(|block| block.start)
+            else {
+                continue;
+            };
+            let kind = match jump.jump_type {
+                JumpType::Conditional => EdgeKind::ConditionalTrue,
+                JumpType::Unconditional => EdgeKind::Unconditional,
+            };
+            // A JUMPDEST always starts a block.
+            self.add_edge(from, jump.target.value, kind);
+            self.unresolved.remove(&from);
+        }
+    }
+
+    fn add_edge(&mut self, from: Hex, to: Hex, kind: EdgeKind) {
+        if self.blocks.contains_key(&to) {
+            self.edges.insert(Edge { from, to, kind });
+        }
+    }
+
+    pub fn entries(&self) -> &[Hex] {
+        &self.entries
+    }
+
+    pub fn blocks(&self) -> impl Iterator<Item = &BasicBlock> {
+        self.blocks.values()
+    }
+
+    // The block the instruction at `offset` is in.
+    pub fn block_containing(&self, offset: Hex) -> Option<&BasicBlock> {
+        self.blocks
+            .range(..=offset)
+            .next_back()
+            .map(|(_, block)| block)
+            .filter(|block| block.end >= offset)
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = &Edge> {
+        self.edges.iter()
+    }
+
+    pub fn successors(&self, start: Hex) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |edge| edge.from == start)
+    }
+
+    pub fn predecessors(&self, start: Hex) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |edge| edge.to == start)
+    }
+
+    pub fn unresolved(&self) -> &BTreeSet<Hex> {
+        &self.unresolved
+    }
+
+    // The blocks reachable from the entries over the known edges. An unresolved jump may reach
+    // any JUMPDEST, so in code with unresolved jumps this is a lower bound.
+    pub fn reachable(&self) -> BTreeSet<Hex> {
+        let mut reached: BTreeSet<Hex> = BTreeSet::new();
+        let mut queue: VecDeque<Hex> = self
+            .entries
+            .iter()
+            .copied()
+            .filter(|entry| self.blocks.contains_key(entry))
+            .collect();
+        while let Some(start) = queue.pop_front() {
+            if reached.insert(start) {
+                queue.extend(self.successors(start).map(|edge| edge.to));
+            }
+        }
+        reached
+    }
+}
+
+// JUMPI ends a block too, so its fallthrough starts a new one.
+fn ends_block(instruction: &Instruction) -> bool {
+    instruction.opcode.halts
+        || matches!(
+            instruction.opcode.code,
+            OpCodes::JUMP | OpCodes::JUMPI | OpCodes::RJUMP | OpCodes::RJUMPI | OpCodes::RJUMPV
+        )
+}
+
+fn next_offset(instruction: &Instruction) -> Hex {
+    instruction.index + Hex::from(instruction.args.len() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{flow_parser::FlowParser, hex::Hex, testing::parse};
+
+    use super::{Cfg, Edge, EdgeKind};
+
+    fn successors(cfg: &Cfg, start: u128) -> Vec<(u128, EdgeKind)> {
+        cfg.successors(Hex::new(start))
+            .map(|edge| (edge.to.as_usize() as u128, edge.kind))
+            .collect()
+    }
+
+    #[test]
+    fn blocks_and_edges() {
+        let parser = parse(
+            "
+            PUSH1 0x01
+            PUSH1 taken
+            JUMPI
+            PUSH1 0x00
+            fallthrough:
+            JUMPDEST
+            STOP
+            taken:
+            JUMPDEST
+            PUSH1 fallthrough
+            JUMP
+            dead:
+            JUMPDEST
+            STOP
+            ",
+        );
+        let cfg = Cfg::new(&parser);
+        let starts: Vec<Hex> = cfg.blocks().map(|block| block.start).collect();
+        assert_eq!(starts, [0, 5, 7, 9, 0xd].map(Hex::new).to_vec());
+        assert_eq!(cfg.block_containing(Hex::new(0)).unwrap().end, Hex::new(4));
+        assert_eq!(
+            successors(&cfg, 0),
+            [
+                (5, EdgeKind::ConditionalFalse),
+                (9, EdgeKind::ConditionalTrue)
+            ]
+        );
+        assert_eq!(successors(&cfg, 5), [(7, EdgeKind::Fallthrough)]);
+        assert_eq!(successors(&cfg, 9), [(7, EdgeKind::Unconditional)]);
+        let predecessors: Vec<Edge> = cfg.predecessors(Hex::new(7)).copied().collect();
+        assert_eq!(predecessors.len(), 2);
+        assert_eq!(
+            cfg.block_containing(Hex::new(0xb)).unwrap().start,
+            Hex::new(9)
+        );
+
+        assert!(cfg.unresolved().is_empty());
+        assert!(cfg.reachable().contains(&Hex::new(7)));
+        assert!(!cfg.reachable().contains(&Hex::new(0xd)));
+    }
+
+    #[test]
+    fn resolve_jumps_from_flows() {
+        // The target is computed, so only executing the code finds it.
+        let parser = parse(
+            "
+            PUSH1 0x02
+            PUSH1 0x04
+            ADD
+            JUMP
+            JUMPDEST
+            STOP
+            ",
+        );
+        let mut cfg = Cfg::new(&parser);
+        assert_eq!(
+            cfg.unresolved().iter().copied().collect::<Vec<_>>(),
+            [Hex::ZERO]
+        );
+        assert!(!cfg.reachable().contains(&Hex::new(6)));
+
+        let mut flow_parser = FlowParser::new(parser.get_instructions(), parser.get_bytecode());
+        flow_parser.parse_flows();
+        cfg.add_flows(&parser, flow_parser.flows());
+        assert!(cfg.unresolved().is_empty());
+        assert_eq!(successors(&cfg, 0), [(6, EdgeKind::Unconditional)]);
+        assert!(cfg.reachable().contains(&Hex::new(6)));
+    }
+
+    #[test]
+    fn resolve_jumps_after_jumpi() {
+        // The JUMPI does not end the instruction set the flow executes, so the JUMP is in a later
+        // block of the same set.
+        let parser = parse(
+            "
+            PUSH1 0x00
+            PUSH1 skip
+            JUMPI
+            PUSH1 0x04
+            PUSH1 0x08
+            ADD
+            JUMP
+            skip:
+            JUMPDEST
+            JUMPDEST
+            STOP
+            ",
+        );
+        let mut cfg = Cfg::new(&parser);
+        assert_eq!(
+            cfg.unresolved().iter().copied().collect::<Vec<_>>(),
+            [Hex::new(5)]
+        );
+
+        let mut flow_parser = FlowParser::new(parser.get_instructions(), parser.get_bytecode());
+        flow_parser.parse_flows();
+        cfg.add_flows(&parser, flow_parser.flows());
+        assert!(cfg.unresolved().is_empty());
+        assert_eq!(
+            successors(&cfg, 0),
+            [
+                (5, EdgeKind::ConditionalFalse),
+                (0xb, EdgeKind::ConditionalTrue)
+            ]
+        );
+        assert_eq!(successors(&cfg, 5), [(0xc, EdgeKind::Unconditional)]);
+    }
+
+    #[test]
+    fn halting_jumps_stay_unresolved() {
+        // The caller is symbolic, and 0x00 is the PUSH1 rather than a JUMPDEST.
+        for source in ["CALLER\nJUMP", "PUSH1 0x00\nJUMP"] {
+            let parser = parse(source);
+            let mut cfg = Cfg::new(&parser);
+            let mut flow_parser = FlowParser::new(parser.get_instructions(), parser.get_bytecode());
+            flow_parser.parse_flows();
+            assert_eq!(flow_parser.flows().len(), 1);
+            cfg.add_flows(&parser, flow_parser.flows());
+            assert_eq!(
+                cfg.unresolved().iter().copied().collect::<Vec<_>>(),
+                [Hex::ZERO]
+            );
+            assert_eq!(cfg.edges().count(), 0);
+        }
+    }
+}
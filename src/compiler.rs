@@ -0,0 +1,221 @@
+use std::fmt;
+
+use crate::{opcode::OpCodes, parser::Parser};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompilerFamily {
+    Solc,
+    Vyper,
+    Huff,
+    Unknown,
+}
+
+// What the compiler was recognised by, from most to least reliable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evidence {
+    Metadata,
+    // solc starts every contract by storing the free memory pointer, `6080604052`.
+    FreeMemoryPointer,
+    // Vyper before 0.3 stores the calldata at 0x1c, so the selector is the last 4 bytes of the
+    // first word of memory.
+    SelectorStore,
+    // Code without metadata that starts by shifting the selector out of the calldata, which is
+    // what Huff contracts do. Vyper 0.3 and later, and solc output with its metadata stripped,
+    // can start the same way, so this is only a guess.
+    SelectorShift,
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compiler {
+    pub family: CompilerFamily,
+    // The exact version from the metadata, or a range when it is derived from the code.
+    pub version: Option<String>,
+    pub evidence: Evidence,
+    // PUSH0 only exists from Shanghai on, so code using it was compiled for Shanghai or later.
+    pub uses_push0: bool,
+}
+
+impl Compiler {
+    // Whether the family is only suggested by an idiom other compilers share.
+    pub fn is_guess(&self) -> bool {
+        self.evidence == Evidence::SelectorShift
+    }
+}
+
+impl fmt::Display for Compiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let family = match self.family {
+            CompilerFamily::Solc => "solc",
+            CompilerFamily::Vyper => "vyper",
+            CompilerFamily::Huff => "huff",
+            CompilerFamily::Unknown => "unknown",
+        };
+        write!(f, "{}", family)?;
+        if self.is_guess() {
+            write!(f, "?")?;
+        }
+        if let Some(version) = &self.version {
+            write!(f, " {}", version)?;
+        }
+        let evidence = match self.evidence {
+            Evidence::Metadata => "metadata",
+            Evidence::FreeMemoryPointer => "free memory pointer prologue",
+            Evidence::SelectorStore => "selector stored at 0x1c",
+            Evidence::SelectorShift => "selector shift without metadata",
+            Evidence::None => return Ok(()),
+        };
+        let from = if self.is_guess() {
+            "guessed from"
+        } else {
+            "from"
+        };
+        write!(f, " ({} {})", from, evidence)
+    }
+}
+
+// PUSH1 0x80 PUSH1 0x40 MSTORE
+const FREE_MEMORY_POINTER: [u8; 5] = [0x60, 0x80, 0x60, 0x40, 0x52];
+// PUSH1 0x00 CALLDATALOAD PUSH1 0x1c MSTORE
+const SELECTOR_STORE: [u8; 6] = [0x60, 0x00, 0x35, 0x60, 0x1c, 0x52];
+// CALLDATALOAD PUSH1 0xe0 SHR, after pushing a zero with PUSH1 or PUSH0.
+const SELECTOR_SHIFT: [u8; 4] = [0x35, 0x60, 0xe0, 0x1c];
+
+// Reports the compiler that produced the bytecode of `parser`, from its metadata when it has
+// any and from idioms in the code otherwise. The idioms are guesses, so a contract can be
+// written to look like it came from another compiler.
+pub fn fingerprint(parser: &Parser) -> Compiler {
+    let uses_push0 = parser
+        .get_instructions()
+        .values()
+        .any(|instruction| instruction.opcode.code == OpCodes::PUSH0);
+    let metadata = parser.get_metadata();
+    let code = &parser.get_bytecode()[..metadata.map_or(parser.get_bytecode().len(), |m| m.start)];
+
+    let (family, version, evidence) = if let Some(solc) = metadata.and_then(|m| m.solc.clone()) {
+        (CompilerFamily::Solc, Some(solc), Evidence::Metadata)
+    } else if let Some(vyper) = metadata.and_then(|m| m.vyper.as_ref()) {
+        (
+            CompilerFamily::Vyper,
+            Some(vyper.version.clone()),
+            Evidence::Metadata,
+        )
+    } else if metadata.is_some() {
+        // Only a hash of the metadata file, which solc versions before 0.5.9 left at that.
+        (
+            CompilerFamily::Solc,
+            Some("<0.5.9".to_string()),
+            Evidence::Metadata,
+        )
+    } else if code.starts_with(&FREE_MEMORY_POINTER) {
+        // PUSH0 is used by default from solc 0.8.20 on.
+        let version = uses_push0.then(|| ">=0.8.20".to_string());
+        (CompilerFamily::Solc, version, Evidence::FreeMemoryPointer)
+    } else if code
+        .windows(SELECTOR_STORE.len())
+        .any(|window| window == SELECTOR_STORE)
+    {
+        (
+            CompilerFamily::Vyper,
+            Some("<0.3".to_string()),
+            Evidence::SelectorStore,
+        )
+    } else if starts_with_selector_shift(code) {
+        (CompilerFamily::Huff, None, Evidence::SelectorShift)
+    } else {
+        (CompilerFamily::Unknown, None, Evidence::None)
+    };
+    Compiler {
+        family,
+        version,
+        evidence,
+        uses_push0,
+    }
+}
+
+fn starts_with_selector_shift(code: &[u8]) -> bool {
+    match code {
+        [0x5f, rest @ ..] | [0x60, 0x00, rest @ ..] => rest.starts_with(&SELECTOR_SHIFT),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{parse, parse_with_metadata, SOLC_METADATA};
+
+    use super::{fingerprint, CompilerFamily, Evidence};
+
+    #[test]
+    fn from_metadata() {
+        let compiler = fingerprint(&parse_with_metadata(
+            "PUSH1 0x80\nPUSH1 0x40\nMSTORE",
+            &SOLC_METADATA,
+        ));
+        assert_eq!(compiler.family, CompilerFamily::Solc);
+        assert_eq!(compiler.version.as_deref(), Some("0.8.19"));
+        assert_eq!(compiler.to_string(), "solc 0.8.19 (from metadata)");
+
+        // {"vyper": [0, 3, 4]}
+        let vyper = [
+            0xa1, 0x65, 0x76, 0x79, 0x70, 0x65, 0x72, 0x83, 0x00, 0x03, 0x04, 0x00, 0x0b,
+        ];
+        let compiler = fingerprint(&parse_with_metadata("STOP", &vyper));
+        assert_eq!(compiler.family, CompilerFamily::Vyper);
+        assert_eq!(compiler.version.as_deref(), Some("0.3.4"));
+    }
+
+    #[test]
+    fn from_code() {
+        let compiler = fingerprint(&parse("PUSH1 0x80\nPUSH1 0x40\nMSTORE\nPUSH0"));
+        assert_eq!(compiler.family, CompilerFamily::Solc);
+        assert_eq!(compiler.evidence, Evidence::FreeMemoryPointer);
+        assert_eq!(compiler.version.as_deref(), Some(">=0.8.20"));
+        assert!(compiler.uses_push0);
+
+        let compiler = fingerprint(&parse(
+            "PUSH1 0x04\nCALLDATASIZE\nLT\nPUSH1 0x00\nCALLDATALOAD\nPUSH1 0x1c\nMSTORE",
+        ));
+        assert_eq!(compiler.family, CompilerFamily::Vyper);
+        assert_eq!(compiler.evidence, Evidence::SelectorStore);
+
+        let compiler = fingerprint(&parse("PUSH0\nCALLDATALOAD\nPUSH1 0xe0\nSHR"));
+        assert_eq!(compiler.family, CompilerFamily::Huff);
+        assert!(compiler.is_guess());
+
+        let compiler = fingerprint(&parse("PUSH1 0x01\nSTOP"));
+        assert_eq!(compiler.family, CompilerFamily::Unknown);
+        assert_eq!(compiler.to_string(), "unknown");
+        assert!(!compiler.uses_push0);
+    }
+
+    #[test]
+    fn selector_shift_is_a_guess() {
+        // The selector dispatch of Vyper 0.3, with its metadata stripped, looks like Huff.
+        let compiler = fingerprint(&parse(
+            "
+            PUSH0
+            CALLDATALOAD
+            PUSH1 0xe0
+            SHR
+            PUSH4 0xa9059cbb
+            DUP2
+            XOR
+            PUSH2 0x0020
+            JUMPI
+            ",
+        ));
+        assert!(compiler.is_guess());
+        assert_eq!(
+            compiler.to_string(),
+            "huff? (guessed from selector shift without metadata)"
+        );
+
+        // Solc never starts that way, so its prologue is not a guess.
+        let compiler = fingerprint(&parse(
+            "PUSH1 0x80\nPUSH1 0x40\nMSTORE\nPUSH0\nCALLDATALOAD\nPUSH1 0xe0\nSHR",
+        ));
+        assert_eq!(compiler.family, CompilerFamily::Solc);
+        assert!(!compiler.is_guess());
+    }
+}
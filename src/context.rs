@@ -0,0 +1,229 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use serde::Deserialize;
+
+use crate::{calldata::CallData, hex::Hex};
+
+// The block a transaction is executed in. Fields that are None are not known.
+#[derive(Debug, Clone, Default)]
+pub struct BlockContext {
+    pub coinbase: Option<Hex>,
+    pub timestamp: Option<Hex>,
+    pub number: Option<Hex>,
+    // DIFFICULTY before the merge, PREVRANDAO after it.
+    pub prevrandao: Option<Hex>,
+    pub gas_limit: Option<Hex>,
+    pub chain_id: Option<Hex>,
+    pub base_fee: Option<Hex>,
+    pub blob_base_fee: Option<Hex>,
+}
+
+// Everything about the transaction being analysed that is not part of the bytecode itself.
+// Fields that are None are not known.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionContext {
+    pub callvalue: Option<Hex>,
+    pub calldata: CallData,
+    pub caller: Option<Hex>,
+    pub origin: Option<Hex>,
+    pub address: Option<Hex>,
+    pub gas_price: Option<Hex>,
+    pub block: BlockContext,
+    // Versioned hashes of the blobs carried by the transaction, or None when not known.
+    pub blob_hashes: Option<Vec<Hex>>,
+    // Balances of the accounts that are known, by address.
+    pub balances: HashMap<Hex, Hex>,
+}
+
+// A value in an environment file, either a number or a string holding a decimal or
+// 0x-prefixed hexadecimal number.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EnvironmentValue {
+    Number(u64),
+    Text(String),
+}
+
+impl TryFrom<&EnvironmentValue> for Hex {
+    type Error = Error;
+
+    fn try_from(value: &EnvironmentValue) -> Result<Self, Self::Error> {
+        match value {
+            EnvironmentValue::Number(value) => Ok(Hex::from(*value)),
+            EnvironmentValue::Text(value) => parse_number(value),
+        }
+    }
+}
+
+fn parse_number(value: &str) -> Result<Hex, Error> {
+    let result = if let Some(hex) = value.strip_prefix("0x") {
+        Hex::from_str_radix(hex, 16)
+    } else {
+        Hex::from_str_radix(value, 10)
+    };
+    result.map_err(|e| Error::new(ErrorKind::InvalidInput, e))
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct BlockEnvironment {
+    coinbase: Option<EnvironmentValue>,
+    timestamp: Option<EnvironmentValue>,
+    number: Option<EnvironmentValue>,
+    #[serde(alias = "difficulty")]
+    prevrandao: Option<EnvironmentValue>,
+    gas_limit: Option<EnvironmentValue>,
+    chain_id: Option<EnvironmentValue>,
+    base_fee: Option<EnvironmentValue>,
+    blob_base_fee: Option<EnvironmentValue>,
+}
+
+// The layout of an environment file, for example in TOML:
+//
+//     caller = "0x5b38da6a701c568545dcfcb03fcb875f56beddc4"
+//     address = "0xd9145cce52d386f254917e481eb44e9943f39138"
+//
+//     [block]
+//     number = 19000000
+//     chain_id = 1
+//
+//     [balances]
+//     "0xd9145cce52d386f254917e481eb44e9943f39138" = "1000000000000000000"
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Environment {
+    caller: Option<EnvironmentValue>,
+    origin: Option<EnvironmentValue>,
+    address: Option<EnvironmentValue>,
+    gas_price: Option<EnvironmentValue>,
+    blob_hashes: Option<Vec<EnvironmentValue>>,
+    #[serde(default)]
+    block: BlockEnvironment,
+    #[serde(default)]
+    balances: HashMap<String, EnvironmentValue>,
+}
+
+fn optional(value: &Option<EnvironmentValue>) -> Result<Option<Hex>, Error> {
+    value.as_ref().map(Hex::try_from).transpose()
+}
+
+impl ExecutionContext {
+    pub fn new() -> ExecutionContext {
+        ExecutionContext::default()
+    }
+
+    // Reads the environment from a JSON or TOML file, depending on its extension. Fields the
+    // file leaves out stay unknown.
+    pub fn from_environment_file(path: &Path) -> Result<ExecutionContext, Error> {
+        let contents = fs::read_to_string(path)?;
+        let environment: Environment = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&contents)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, e))?,
+            Some("toml") => {
+                toml::from_str(&contents).map_err(|e| Error::new(ErrorKind::InvalidData, e))?
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Environment file {} should be a .json or .toml file",
+                        path.display()
+                    ),
+                ))
+            }
+        };
+        ExecutionContext::from_environment(&environment)
+    }
+
+    fn from_environment(environment: &Environment) -> Result<ExecutionContext, Error> {
+        let block = &environment.block;
+        let mut balances = HashMap::new();
+        for (address, balance) in &environment.balances {
+            balances.insert(parse_number(address)?, Hex::try_from(balance)?);
+        }
+        Ok(ExecutionContext {
+            caller: optional(&environment.caller)?,
+            origin: optional(&environment.origin)?,
+            address: optional(&environment.address)?,
+            gas_price: optional(&environment.gas_price)?,
+            block: BlockContext {
+                coinbase: optional(&block.coinbase)?,
+                timestamp: optional(&block.timestamp)?,
+                number: optional(&block.number)?,
+                prevrandao: optional(&block.prevrandao)?,
+                gas_limit: optional(&block.gas_limit)?,
+                chain_id: optional(&block.chain_id)?,
+                base_fee: optional(&block.base_fee)?,
+                blob_base_fee: optional(&block.blob_base_fee)?,
+            },
+            blob_hashes: environment
+                .blob_hashes
+                .as_ref()
+                .map(|hashes| hashes.iter().map(Hex::try_from).collect())
+                .transpose()?,
+            balances,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex::Hex;
+
+    use super::{Environment, ExecutionContext};
+
+    #[test]
+    fn parse_toml_environment() {
+        let environment: Environment = toml::from_str(
+            r#"
+            caller = "0x5b38da6a701c568545dcfcb03fcb875f56beddc4"
+            gas_price = "1000"
+
+            [block]
+            number = 19000000
+            difficulty = "0x10"
+
+            [balances]
+            "0x01" = 5
+            "#,
+        )
+        .unwrap();
+        let context = ExecutionContext::from_environment(&environment).unwrap();
+        assert_eq!(
+            context.caller,
+            Some(Hex::from_str_radix("5b38da6a701c568545dcfcb03fcb875f56beddc4", 16).unwrap())
+        );
+        assert_eq!(context.gas_price, Some(Hex::new(1000)));
+        assert_eq!(context.block.number, Some(Hex::new(19000000)));
+        assert_eq!(context.block.prevrandao, Some(Hex::new(0x10)));
+        assert_eq!(context.balances.get(&Hex::new(1)), Some(&Hex::new(5)));
+        assert_eq!(context.origin, None);
+        assert_eq!(context.block.chain_id, None);
+    }
+
+    #[test]
+    fn parse_json_environment() {
+        let environment: Environment = serde_json::from_str(
+            r#"{"address": "0xff", "blob_hashes": ["0x01"], "block": {"chain_id": 1}}"#,
+        )
+        .unwrap();
+        let context = ExecutionContext::from_environment(&environment).unwrap();
+        assert_eq!(context.address, Some(Hex::new(0xff)));
+        assert_eq!(context.block.chain_id, Some(Hex::new(1)));
+        assert_eq!(context.blob_hashes, Some(vec![Hex::new(1)]));
+        assert_eq!(context.caller, None);
+    }
+
+    #[test]
+    fn reject_invalid_environment() {
+        assert!(toml::from_str::<Environment>("sender = \"0x01\"").is_err());
+        let environment: Environment = toml::from_str("caller = \"0xzz\"").unwrap();
+        assert!(ExecutionContext::from_environment(&environment).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    hex::Hex,
+    instruction::Instruction,
+    opcode::{push_width, Hardfork, OpCodes},
+    parser::{DisassemblyError, Parser},
+};
+
+// Creation bytecode split into its parts. The constructor copies the runtime code out of
+// itself and returns it, and the ABI-encoded constructor arguments are appended after the
+// runtime, and so after the metadata at its end:
+//
+//   PUSH2 <size> DUP1 PUSH2 <offset> PUSH0 CODECOPY PUSH0 RETURN INVALID <runtime> <arguments>
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deployment {
+    // The offset of the CODECOPY that copies the runtime.
+    pub codecopy: Hex,
+    pub runtime_offset: usize,
+    pub constructor: Vec<u8>,
+    pub runtime: Vec<u8>,
+    pub arguments: Vec<u8>,
+}
+
+impl Deployment {
+    // Finds the instruction set of the constructor that ends by returning code it copied from
+    // itself. The stack of each set is evaluated on its own, so the offset and size of the
+    // runtime have to be pushed in the same set, which is how compilers emit them.
+    pub fn find(parser: &Parser) -> Option<Deployment> {
+        let bytecode = parser.get_bytecode();
+        let instructions = parser.get_instructions();
+        let sets: BTreeMap<_, _> = parser.get_instruction_sets().into_iter().collect();
+        sets.values().find_map(|set| {
+            let (codecopy, offset, size) = returned_code(instructions, set.start, bytecode.len())?;
+            let end = offset.checked_add(size)?;
+            if offset == 0 || size == 0 || end > bytecode.len() {
+                return None;
+            }
+            Some(Deployment {
+                codecopy,
+                runtime_offset: offset,
+                constructor: bytecode[..offset].to_vec(),
+                runtime: bytecode[offset..end].to_vec(),
+                arguments: bytecode[end..].to_vec(),
+            })
+        })
+    }
+
+    pub fn constructor_parser(&self, hardfork: Hardfork) -> Result<Parser, DisassemblyError> {
+        Parser::from_bytes(&self.constructor, hardfork)
+    }
+
+    pub fn runtime_parser(&self, hardfork: Hardfork) -> Result<Parser, DisassemblyError> {
+        Parser::from_bytes(&self.runtime, hardfork)
+    }
+
+    // The constructor arguments as 32-byte ABI words. Without the ABI the types are unknown,
+    // so dynamic values show up as their offset, length and contents. None when the arguments
+    // are not whole words, in which case they are not ABI-encoded.
+    pub fn argument_words(&self) -> Option<Vec<Hex>> {
+        if !self.arguments.len().is_multiple_of(32) {
+            return None;
+        }
+        Some(self.arguments.chunks(32).map(Hex::from_be_bytes).collect())
+    }
+}
+
+// Evaluates the instruction set at `start` with only the values it pushes itself known, and
+// returns the CODECOPY offset and the code offset and size it copied when the set returns
+// exactly the memory it copied to.
+fn returned_code(
+    instructions: &HashMap<Hex, Instruction>,
+    start: Hex,
+    code_size: usize,
+) -> Option<(Hex, usize, usize)> {
+    let mut stack: Vec<Option<Hex>> = Vec::new();
+    let mut copied = None;
+    let mut pc = start;
+    while let Some(instruction) = instructions.get(&pc) {
+        let byte = instruction.opcode_byte();
+        match instruction.opcode.code {
+            OpCodes::CODESIZE => stack.push(Some(Hex::from(code_size))),
+            OpCodes::CODECOPY => {
+                let dest = pop(&mut stack);
+                let offset = pop(&mut stack);
+                let size = pop(&mut stack);
+                copied = match (dest, offset, size) {
+                    (Some(dest), Some(offset), Some(size)) => {
+                        Some((instruction.index, dest, offset, size))
+                    }
+                    _ => None,
+                };
+            }
+            OpCodes::RETURN => {
+                let offset = pop(&mut stack)?;
+                let returned = pop(&mut stack)?;
+                let (codecopy, dest, code_offset, size) = copied?;
+                // Only the copied code is the runtime, so the set has to return exactly it.
+                // Memory written in between, such as immutables, is part of it.
+                if offset != dest || returned != size {
+                    return None;
+                }
+                let code_offset = usize::try_from(code_offset).ok()?;
+                let size = usize::try_from(size).ok()?;
+                return Some((codecopy, code_offset, size));
+            }
+            _ if push_width(&instruction.opcode.code).is_some() => {
+                stack.push(Some(instruction.push_value()))
+            }
+            _ if (0x80..=0x8f).contains(&byte) => {
+                let depth = usize::from(byte - 0x7f);
+                let value = stack
+                    .len()
+                    .checked_sub(depth)
+                    .and_then(|index| stack[index]);
+                stack.push(value);
+            }
+            _ if (0x90..=0x9f).contains(&byte) => {
+                let depth = usize::from(byte - 0x8f);
+                // Values from before the set are unknown, so make room for them.
+                while stack.len() <= depth {
+                    stack.insert(0, None);
+                }
+                let top = stack.len() - 1;
+                stack.swap(top, top - depth);
+            }
+            _ if instruction.opcode.halts || instruction.opcode.jumps => return None,
+            _ => {
+                for _ in 0..instruction.opcode.stack_inputs {
+                    pop(&mut stack);
+                }
+                for _ in 0..instruction.opcode.stack_outputs {
+                    stack.push(None);
+                }
+            }
+        }
+        pc += Hex::from(instruction.args.len() + 1);
+    }
+    None
+}
+
+fn pop(stack: &mut Vec<Option<Hex>>) -> Option<Hex> {
+    stack.pop().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assembler::assemble, hex::Hex, opcode::Hardfork, parser::Parser, testing::SOLC_METADATA,
+    };
+
+    use super::Deployment;
+
+    #[test]
+    fn split_creation_code() {
+        let mut runtime = assemble("PUSH1 0x80\nPUSH1 0x40\nMSTORE\nSTOP").unwrap();
+        runtime.extend_from_slice(&SOLC_METADATA);
+        let constructor = assemble(&format!(
+            "
+            CALLVALUE
+            DUP1
+            ISZERO
+            PUSH2 0x000a
+            JUMPI
+            PUSH0
+            DUP1
+            REVERT
+            JUMPDEST
+            POP
+            PUSH2 {:#06x}
+            DUP1
+            PUSH2 0x0018
+            PUSH0
+            CODECOPY
+            PUSH0
+            RETURN
+            INVALID
+            ",
+            runtime.len()
+        ))
+        .unwrap();
+        assert_eq!(constructor.len(), 0x18);
+        let mut bytecode = [constructor.clone(), runtime.clone()].concat();
+        bytecode.extend_from_slice(&Hex::new(0xc0de).to_be_bytes());
+
+        let deployment =
+            Deployment::find(&Parser::from_bytes(&bytecode, Hardfork::Cancun).unwrap()).unwrap();
+        assert_eq!(deployment.codecopy, Hex::new(0x14));
+        assert_eq!(deployment.runtime_offset, 0x18);
+        assert_eq!(deployment.constructor, constructor);
+        assert_eq!(deployment.runtime, runtime);
+        assert_eq!(deployment.argument_words(), Some(vec![Hex::new(0xc0de)]));
+
+        let runtime = deployment.runtime_parser(Hardfork::Cancun).unwrap();
+        assert_eq!(
+            runtime.get_metadata().unwrap().solc.as_deref(),
+            Some("0.8.19")
+        );
+        assert_eq!(runtime.get_instructions().len(), 4);
+    }
+
+    #[test]
+    fn runtime_code_is_not_deployed() {
+        // Returning memory that was not copied from the code is not a deployment.
+        let bytecode = assemble(
+            "PUSH1 0x20\nPUSH1 0x00\nPUSH1 0x00\nCODECOPY\nPUSH1 0x20\nPUSH1 0x20\nRETURN",
+        )
+        .unwrap();
+        assert_eq!(
+            Deployment::find(&Parser::from_bytes(&bytecode, Hardfork::Cancun).unwrap()),
+            None
+        );
+        let bytecode = assemble("PUSH1 0x80\nPUSH1 0x40\nMSTORE\nSTOP").unwrap();
+        assert_eq!(
+            Deployment::find(&Parser::from_bytes(&bytecode, Hardfork::Cancun).unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn immutables_are_written_before_return() {
+        // The runtime is copied to 0x80, an immutable is written into it, and it is returned.
+        let constructor = assemble(
+            "
+            PUSH1 0x24
+            PUSH1 0x11
+            PUSH1 0x80
+            CODECOPY
+            CALLER
+            PUSH1 0x84
+            MSTORE
+            PUSH1 0x24
+            PUSH1 0x80
+            RETURN
+            INVALID
+            ",
+        )
+        .unwrap();
+        assert_eq!(constructor.len(), 0x11);
+        let runtime = [0x5f; 0x24];
+        let bytecode = [constructor.clone(), runtime.to_vec()].concat();
+
+        let deployment =
+            Deployment::find(&Parser::from_bytes(&bytecode, Hardfork::Cancun).unwrap()).unwrap();
+        assert_eq!(deployment.codecopy, Hex::new(6));
+        assert_eq!(deployment.runtime_offset, 0x11);
+        assert_eq!(deployment.runtime, runtime);
+        assert!(deployment.arguments.is_empty());
+    }
+
+    #[test]
+    fn return_size_differs_from_copy() {
+        // Returning less or more than was copied does not deploy the copied code.
+        for (returned, deployed) in [("0x10", false), ("0x20", true), ("0x30", false)] {
+            let bytecode = assemble(&format!(
+                "
+                PUSH1 0x20
+                PUSH1 0x0d
+                PUSH1 0x00
+                CODECOPY
+                PUSH1 {}
+                PUSH1 0x00
+                RETURN
+                INVALID
+                ",
+                returned
+            ))
+            .unwrap();
+            assert_eq!(bytecode.len(), 0x0d);
+            let bytecode = [bytecode, vec![0x5f; 0x20]].concat();
+            assert_eq!(
+                Deployment::find(&Parser::from_bytes(&bytecode, Hardfork::Cancun).unwrap())
+                    .is_some(),
+                deployed
+            );
+        }
+    }
+}
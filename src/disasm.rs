@@ -0,0 +1,116 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+use crate::{
+    cbor::Metadata,
+    hex::Hex,
+    instruction::{Instruction, InstructionSet},
+    opcode::OpCodes,
+};
+
+// Writes a linear disassembly listing, one instruction per line in the order of their offsets:
+//
+//     ; block 0x0000
+//     0000  6080          PUSH1 0x80
+//     ...
+//
+//     ; block 0x000c
+//     loc_000c:
+//     000c  5b            JUMPDEST
+//
+// Every basic block starts with a `; block` comment and every JUMPDEST is preceded by a label.
+// Metadata appended by the compiler is not disassembled, but described in a last comment.
+// The format only depends on the bytecode, so listings can be diffed across compiler versions.
+pub fn write_listing(
+    out: &mut impl Write,
+    instructions: &HashMap<Hex, Instruction>,
+    instruction_sets: &HashMap<Hex, InstructionSet>,
+    metadata: Option<&Metadata>,
+) -> io::Result<()> {
+    let instructions: BTreeMap<_, _> = instructions.iter().collect();
+    for (offset, instruction) in instructions {
+        if instruction_sets.contains_key(offset) {
+            if !offset.is_zero() {
+                writeln!(out)?;
+            }
+            writeln!(out, "; block 0x{:04x}", offset)?;
+        }
+        if instruction.opcode.code == OpCodes::JUMPDEST {
+            writeln!(out, "loc_{:04x}:", offset)?;
+        }
+        writeln!(out, "{}", format_instruction(instruction).trim_end())?;
+    }
+    if let Some(metadata) = metadata {
+        writeln!(out)?;
+        writeln!(out, "; metadata 0x{:04x}: {}", metadata.start, metadata)?;
+    }
+    Ok(())
+}
+
+pub fn format_instruction(instruction: &Instruction) -> String {
+    let immediate: String = instruction
+        .args
+        .iter()
+        .map(|arg| format!("{:02x}", arg))
+        .collect();
+    let raw = format!("{:02x}{}", instruction.opcode_byte(), immediate);
+    let immediate = if immediate.is_empty() {
+        immediate
+    } else {
+        format!("0x{}", immediate)
+    };
+    format!(
+        "{:04x}  {:<12}  {} {}",
+        instruction.index, raw, instruction.opcode.short_name, immediate
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{opcode::Hardfork, parser::Parser, testing::SOLC_METADATA};
+
+    use super::write_listing;
+
+    #[test]
+    fn listing() {
+        let code = [
+            0x60, 0x04, // PUSH1 0x04
+            0x56, // JUMP
+            0x0c, // not an opcode
+            0x5b, // JUMPDEST
+            0x61, 0x00, 0x2a, // PUSH2 0x002a
+            0x00, // STOP
+        ];
+        let parser =
+            Parser::from_bytes(&[&code[..], &SOLC_METADATA].concat(), Hardfork::Cancun).unwrap();
+        let mut out = Vec::new();
+        write_listing(
+            &mut out,
+            parser.get_instructions(),
+            &parser.get_instruction_sets(),
+            parser.get_metadata(),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+; block 0x0000
+0000  6004          PUSH1 0x04
+0002  56            JUMP
+
+; block 0x0003
+0003  0c            INVALID
+
+; block 0x0004
+loc_0004:
+0004  5b            JUMPDEST
+0005  61002a        PUSH2 0x002a
+0008  00            STOP
+
+; metadata 0x0009: solc 0.8.19
+"
+        );
+    }
+}
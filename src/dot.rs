@@ -0,0 +1,190 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{self, Write},
+};
+
+use crate::{
+    cfg::{Cfg, EdgeKind},
+    disasm::format_instruction,
+    hex::Hex,
+    instruction::Instruction,
+};
+
+// A program to draw, named so the constructor and runtime of creation code can share a graph.
+pub struct Program<'a> {
+    pub name: &'a str,
+    pub cfg: &'a Cfg,
+    pub instructions: &'a HashMap<Hex, Instruction>,
+}
+
+// Writes the control-flow graphs of `programs` as a Graphviz digraph, one cluster per program:
+//
+//     dot -Tsvg graph.dot > graph.svg
+//
+// Blocks are boxes labelled with their disassembly, and blocks that are not reachable over the
+// known edges are grey. Edges are green for taken conditional jumps, red for the fallthrough of
+// a conditional jump that is not taken, blue for unconditional jumps and black for running into
+// the next block. Jumps whose target is unknown lead to an "unknown" node.
+pub fn write_dot(out: &mut impl Write, programs: &[Program]) -> io::Result<()> {
+    writeln!(out, "digraph cfg {{")?;
+    writeln!(out, "    node [shape=box, fontname=monospace];")?;
+    for program in programs {
+        write_cluster(out, program)?;
+    }
+    writeln!(out, "}}")
+}
+
+fn write_cluster(out: &mut impl Write, program: &Program) -> io::Result<()> {
+    let name = program.name;
+    let reachable: BTreeSet<Hex> = program.cfg.reachable();
+    writeln!(out, "    subgraph cluster_{} {{", name)?;
+    writeln!(out, "        label=\"{}\";", name)?;
+    for block in program.cfg.blocks() {
+        let label: String = block
+            .instructions
+            .iter()
+            .map(|offset| {
+                format!(
+                    "{}\\l",
+                    escape(format_instruction(&program.instructions[offset]).trim_end())
+                )
+            })
+            .collect();
+        let style = if reachable.contains(&block.start) {
+            ""
+        } else {
+            ", style=filled, fillcolor=lightgrey"
+        };
+        writeln!(
+            out,
+            "        {}_{:04x} [label=\"{}\"{}];",
+            name, block.start, label, style
+        )?;
+    }
+    for edge in program.cfg.edges() {
+        let (color, label) = match edge.kind {
+            EdgeKind::Fallthrough => ("black", ""),
+            EdgeKind::ConditionalTrue => ("green", "true"),
+            EdgeKind::ConditionalFalse => ("red", "false"),
+            EdgeKind::Unconditional => ("blue", ""),
+        };
+        writeln!(
+            out,
+            "        {}_{:04x} -> {}_{:04x} [color={}, label=\"{}\"];",
+            name, edge.from, name, edge.to, color, label
+        )?;
+    }
+    if !program.cfg.unresolved().is_empty() {
+        writeln!(
+            out,
+            "        {}_unknown [label=\"unknown\", shape=octagon, style=dashed];",
+            name
+        )?;
+        for start in program.cfg.unresolved() {
+            writeln!(
+                out,
+                "        {}_{:04x} -> {}_unknown [style=dashed];",
+                name, start, name
+            )?;
+        }
+    }
+    writeln!(out, "    }}")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cfg::Cfg, testing::parse};
+
+    use super::{write_dot, Program};
+
+    #[test]
+    fn graph() {
+        let parser = parse(
+            "
+            PUSH1 0x01
+            PUSH1 taken
+            JUMPI
+            CALLDATALOAD
+            JUMP
+            taken:
+            JUMPDEST
+            STOP
+            ",
+        );
+        let cfg = Cfg::new(&parser);
+        let mut out = Vec::new();
+        write_dot(
+            &mut out,
+            &[Program {
+                name: "runtime",
+                cfg: &cfg,
+                instructions: parser.get_instructions(),
+            }],
+        )
+        .unwrap();
+        let dot = String::from_utf8(out).unwrap();
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(dot.contains("    subgraph cluster_runtime {\n"));
+        assert!(dot.contains(
+            "        runtime_0000 [label=\"0000  6001          PUSH1 0x01\\l\
+             0002  6007          PUSH1 0x07\\l0004  57            JUMPI\\l\"];\n"
+        ));
+        assert!(dot.contains("runtime_0000 -> runtime_0007 [color=green, label=\"true\"];"));
+        assert!(dot.contains("runtime_0000 -> runtime_0005 [color=red, label=\"false\"];"));
+        // The target of the JUMP is loaded from the calldata.
+        assert!(dot.contains(
+            "        runtime_unknown [label=\"unknown\", shape=octagon, style=dashed];\n"
+        ));
+        assert!(dot.contains("runtime_0005 -> runtime_unknown [style=dashed];"));
+        assert!(!dot.contains("runtime_0007 -> runtime_unknown"));
+        assert!(dot.ends_with("    }\n}\n"));
+    }
+
+    #[test]
+    fn edge_styles() {
+        let parser = parse(
+            "
+            PUSH1 next
+            JUMP
+            dead:
+            JUMPDEST
+            STOP
+            next:
+            JUMPDEST
+            PUSH1 0x00
+            POP
+            JUMPDEST
+            STOP
+            ",
+        );
+        let cfg = Cfg::new(&parser);
+        let mut out = Vec::new();
+        write_dot(
+            &mut out,
+            &[Program {
+                name: "code",
+                cfg: &cfg,
+                instructions: parser.get_instructions(),
+            }],
+        )
+        .unwrap();
+        let dot = String::from_utf8(out).unwrap();
+        // Every jump is resolved, so there is no unknown node.
+        assert!(!dot.contains("unknown"));
+        assert!(dot.contains("code_0000 -> code_0005 [color=blue, label=\"\"];"));
+        assert!(dot.contains("code_0005 -> code_0009 [color=black, label=\"\"];"));
+        // Nothing jumps to `dead` and the JUMP does not run into it.
+        assert!(dot.contains(
+            "        code_0003 [label=\"0003  5b            JUMPDEST\\l\
+             0004  00            STOP\\l\", style=filled, fillcolor=lightgrey];\n"
+        ));
+        assert!(dot.contains(
+            "        code_0009 [label=\"0009  5b            JUMPDEST\\l\
+             000a  00            STOP\\l\"];\n"
+        ));
+    }
+}
@@ -0,0 +1,724 @@
+use std::{collections::HashMap, fmt};
+
+use crate::opcode::{eof_opcodes_for, Hardfork, OpCode, OpCodes};
+
+// Code in the EVM Object Format starts with these bytes, which no legacy contract can start
+// with since London.
+pub const MAGIC: [u8; 2] = [0xef, 0x00];
+const VERSION: u8 = 1;
+
+const KIND_TYPES: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_CONTAINER: u8 = 0x03;
+const KIND_DATA: u8 = 0x04;
+const TERMINATOR: u8 = 0x00;
+
+const MAX_CODE_SECTIONS: usize = 1024;
+const MAX_CONTAINER_SECTIONS: usize = 256;
+const MAX_STACK_HEIGHT: usize = 1023;
+const STACK_LIMIT: usize = 1024;
+// The outputs of a code section that never returns to its caller.
+const NON_RETURNING: u8 = 0x80;
+
+// Reasons a container is not valid EOF. Offsets in code are relative to the start of the code
+// section.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EofError {
+    Header {
+        offset: usize,
+        reason: &'static str,
+    },
+    Type {
+        section: usize,
+        reason: &'static str,
+    },
+    Instruction {
+        section: usize,
+        offset: usize,
+        reason: &'static str,
+    },
+    StackHeight {
+        section: usize,
+        offset: usize,
+        reason: &'static str,
+    },
+    Container {
+        index: usize,
+        error: Box<EofError>,
+    },
+}
+
+impl fmt::Display for EofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EofError::Header { offset, reason } => {
+                write!(f, "invalid EOF header at {:#x}: {}", offset, reason)
+            }
+            EofError::Type { section, reason } => {
+                write!(f, "invalid type of code section {}: {}", section, reason)
+            }
+            EofError::Instruction {
+                section,
+                offset,
+                reason,
+            } => write!(f, "code section {} at {:#x}: {}", section, offset, reason),
+            EofError::StackHeight {
+                section,
+                offset,
+                reason,
+            } => write!(
+                f,
+                "code section {} at {:#x}: invalid stack height, {}",
+                section, offset, reason
+            ),
+            EofError::Container { index, error } => {
+                write!(f, "container section {}: {}", index, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EofError {}
+
+// The stack effect of a code section, from the type section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionType {
+    pub inputs: u8,
+    // The number of outputs, or 0x80 for sections that never return.
+    pub outputs: u8,
+    pub max_stack_height: u16,
+}
+
+impl FunctionType {
+    pub fn returns(&self) -> bool {
+        self.outputs != NON_RETURNING
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeSection {
+    // The offset of the code in the container.
+    pub offset: usize,
+    pub function: FunctionType,
+    pub code: Vec<u8>,
+}
+
+// An EOF container (EIP-3540), which splits the code from the data it uses:
+//
+//     ef00 01                  magic and version
+//     01 0004                  type section of 4 bytes per code section
+//     02 0001 0003             1 code section of 3 bytes
+//     03 0001 00000014         optional, 1 container section of 0x14 bytes
+//     04 0000                  data section of 0 bytes
+//     00                       end of the header
+//     00 80 0001               inputs, outputs and max stack height of every code section
+//     ...                      the code, container and data sections
+//
+// The data section of a container that is deployed by another one may be shorter than its
+// header says, in which case the deploying code appends the rest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Container {
+    pub version: u8,
+    pub code_sections: Vec<CodeSection>,
+    pub container_sections: Vec<Vec<u8>>,
+    pub data_offset: usize,
+    pub data: Vec<u8>,
+    pub declared_data_size: usize,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, length: usize, reason: &'static str) -> Result<&[u8], EofError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + length)
+            .ok_or(EofError::Header {
+                offset: self.position,
+                reason,
+            })?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn number(&mut self, size: usize, reason: &'static str) -> Result<usize, EofError> {
+        Ok(self
+            .take(size, reason)?
+            .iter()
+            .fold(0, |value, byte| (value << 8) | *byte as usize))
+    }
+
+    fn expect(&mut self, kind: u8, reason: &'static str) -> Result<(), EofError> {
+        let offset = self.position;
+        if self.number(1, reason)? != kind as usize {
+            return Err(EofError::Header { offset, reason });
+        }
+        Ok(())
+    }
+
+    // Reads the number of sections and their sizes, which are `size` bytes each.
+    fn sizes(
+        &mut self,
+        size: usize,
+        max: usize,
+        reason: &'static str,
+    ) -> Result<Vec<usize>, EofError> {
+        let offset = self.position;
+        let count = self.number(2, reason)?;
+        if count == 0 || count > max {
+            return Err(EofError::Header { offset, reason });
+        }
+        let sizes = (0..count)
+            .map(|_| self.number(size, reason))
+            .collect::<Result<Vec<_>, _>>()?;
+        if sizes.contains(&0) {
+            return Err(EofError::Header { offset, reason });
+        }
+        Ok(sizes)
+    }
+}
+
+impl Container {
+    // Reads the sections of a container. This only checks that the sections are where the
+    // header says they are; `validate` checks the code in them.
+    pub fn parse(bytes: &[u8]) -> Result<Container, EofError> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.take(2, "missing EOF magic")? != MAGIC {
+            return Err(EofError::Header {
+                offset: 0,
+                reason: "missing EOF magic",
+            });
+        }
+        let version = reader.number(1, "missing version")? as u8;
+        if version != VERSION {
+            return Err(EofError::Header {
+                offset: 2,
+                reason: "unsupported version",
+            });
+        }
+        reader.expect(KIND_TYPES, "missing type section")?;
+        let types_offset = reader.position;
+        let types_size = reader.number(2, "missing type section size")?;
+        reader.expect(KIND_CODE, "missing code section")?;
+        let code_sizes = reader.sizes(2, MAX_CODE_SECTIONS, "invalid code section sizes")?;
+        if types_size != code_sizes.len() * 4 {
+            return Err(EofError::Header {
+                offset: types_offset,
+                reason: "type section size does not match the code sections",
+            });
+        }
+        let container_sizes = if bytes.get(reader.position) == Some(&KIND_CONTAINER) {
+            reader.position += 1;
+            reader.sizes(4, MAX_CONTAINER_SECTIONS, "invalid container section sizes")?
+        } else {
+            Vec::new()
+        };
+        reader.expect(KIND_DATA, "missing data section")?;
+        let declared_data_size = reader.number(2, "missing data section size")?;
+        reader.expect(TERMINATOR, "missing header terminator")?;
+
+        let mut functions = Vec::new();
+        for section in 0..code_sizes.len() {
+            let function = reader.take(4, "truncated type section")?;
+            let function = FunctionType {
+                inputs: function[0],
+                outputs: function[1],
+                max_stack_height: u16::from_be_bytes([function[2], function[3]]),
+            };
+            let invalid = |reason| Err(EofError::Type { section, reason });
+            if section == 0 && (function.inputs != 0 || function.returns()) {
+                return invalid("the first code section must take no inputs and not return");
+            }
+            if function.inputs > 0x7f || (function.returns() && function.outputs > 0x7f) {
+                return invalid("too many inputs or outputs");
+            }
+            if function.max_stack_height as usize > MAX_STACK_HEIGHT
+                || function.max_stack_height < function.inputs as u16
+            {
+                return invalid("invalid max stack height");
+            }
+            functions.push(function);
+        }
+        let mut code_sections = Vec::new();
+        for (function, size) in functions.into_iter().zip(code_sizes) {
+            let offset = reader.position;
+            let code = reader.take(size, "truncated code section")?.to_vec();
+            code_sections.push(CodeSection {
+                offset,
+                function,
+                code,
+            });
+        }
+        let container_sections = container_sizes
+            .into_iter()
+            .map(|size| Ok(reader.take(size, "truncated container section")?.to_vec()))
+            .collect::<Result<Vec<_>, EofError>>()?;
+        let data_offset = reader.position;
+        let data = bytes[data_offset..].to_vec();
+        if data.len() > declared_data_size {
+            return Err(EofError::Header {
+                offset: data_offset + declared_data_size,
+                reason: "bytes after the data section",
+            });
+        }
+        Ok(Container {
+            version,
+            code_sections,
+            container_sections,
+            data_offset,
+            data,
+            declared_data_size,
+        })
+    }
+
+    // Checks the EOF validation rules for the code in the container and in the containers in
+    // it: every instruction is defined for EOF, relative jumps land on instructions in the
+    // same section, calls go to existing sections, and the stack height at every instruction
+    // is the same whichever way it is reached.
+    pub fn validate(&self, hardfork: Hardfork) -> Result<(), EofError> {
+        if self.data.len() < self.declared_data_size {
+            return Err(EofError::Header {
+                offset: self.data_offset,
+                reason: "truncated data section",
+            });
+        }
+        self.validate_sections(hardfork)
+    }
+
+    fn validate_sections(&self, hardfork: Hardfork) -> Result<(), EofError> {
+        let opcodes = eof_opcodes_for(hardfork);
+        let functions: Vec<FunctionType> = self
+            .code_sections
+            .iter()
+            .map(|section| section.function)
+            .collect();
+        for (index, section) in self.code_sections.iter().enumerate() {
+            let instructions = decode(&section.code, &opcodes).map_err(|(offset, reason)| {
+                EofError::Instruction {
+                    section: index,
+                    offset,
+                    reason,
+                }
+            })?;
+            validate_instructions(index, section, &instructions, &functions, self)?;
+            validate_stack(index, section, &instructions, &functions)?;
+        }
+        for (index, bytes) in self.container_sections.iter().enumerate() {
+            // Containers that are deployed by this one may have a truncated data section.
+            Container::parse(bytes)
+                .and_then(|container| container.validate_sections(hardfork))
+                .map_err(|error| EofError::Container {
+                    index,
+                    error: Box::new(error),
+                })?;
+        }
+        Ok(())
+    }
+}
+
+// The number of immediate bytes after the opcode at `position` in `code`. Only RJUMPV has a
+// size that depends on the immediate itself.
+pub fn immediate_size(opcode: &OpCode, code: &[u8], position: usize) -> usize {
+    match opcode.code {
+        OpCodes::RJUMPV => code
+            .get(position + 1)
+            .map_or(1, |max_index| 1 + 2 * (*max_index as usize + 1)),
+        _ => opcode.input_arguments as usize,
+    }
+}
+
+// The offset a relative jump lands on, from the offset of the instruction following it.
+pub fn relative_target(next: usize, offset: i16) -> Option<usize> {
+    next.checked_add_signed(offset as isize)
+}
+
+// The relative offsets in the immediate of RJUMP, RJUMPI or RJUMPV.
+pub fn relative_offsets(code: &OpCodes, immediate: &[u8]) -> Vec<i16> {
+    let offsets = match code {
+        OpCodes::RJUMP | OpCodes::RJUMPI => immediate,
+        OpCodes::RJUMPV => immediate.get(1..).unwrap_or_default(),
+        _ => &[],
+    };
+    offsets
+        .chunks_exact(2)
+        .map(|offset| i16::from_be_bytes([offset[0], offset[1]]))
+        .collect()
+}
+
+// The stack inputs and outputs of an instruction, which for some EOF instructions depend on
+// their immediate or on the types of code sections.
+fn stack_effect(opcode: &OpCode, immediate: &[u8], functions: &[FunctionType]) -> (usize, usize) {
+    let first = immediate.first().copied().unwrap_or_default() as usize;
+    match opcode.code {
+        OpCodes::CALLF => {
+            let function = functions[u16::from_be_bytes([immediate[0], immediate[1]]) as usize];
+            (function.inputs as usize, function.outputs as usize)
+        }
+        OpCodes::JUMPF => {
+            let function = functions[u16::from_be_bytes([immediate[0], immediate[1]]) as usize];
+            (function.inputs as usize, 0)
+        }
+        OpCodes::DUPN => (first + 1, first + 2),
+        OpCodes::SWAPN => (first + 2, first + 2),
+        OpCodes::EXCHANGE => {
+            let depth = (first >> 4) + (first & 0x0f) + 3;
+            (depth, depth)
+        }
+        _ => (opcode.stack_inputs as usize, opcode.stack_outputs as usize),
+    }
+}
+
+struct Decoded<'a> {
+    offset: usize,
+    opcode: &'a OpCode,
+    immediate: &'a [u8],
+}
+
+impl Decoded<'_> {
+    fn next(&self) -> usize {
+        self.offset + 1 + self.immediate.len()
+    }
+
+    fn section_index(&self) -> usize {
+        u16::from_be_bytes([self.immediate[0], self.immediate[1]]) as usize
+    }
+}
+
+fn decode<'a>(
+    code: &'a [u8],
+    opcodes: &'a HashMap<OpCodes, OpCode>,
+) -> Result<Vec<Decoded<'a>>, (usize, &'static str)> {
+    let mut instructions = Vec::new();
+    let mut offset = 0;
+    while let Some(byte) = code.get(offset) {
+        let opcode = num_traits::FromPrimitive::from_u8(*byte)
+            .and_then(|code: OpCodes| opcodes.get(&code))
+            .ok_or((offset, "undefined instruction"))?;
+        let end = offset + 1 + immediate_size(opcode, code, offset);
+        let immediate = code
+            .get(offset + 1..end)
+            .ok_or((offset, "truncated immediate"))?;
+        instructions.push(Decoded {
+            offset,
+            opcode,
+            immediate,
+        });
+        offset = end;
+    }
+    Ok(instructions)
+}
+
+// The offsets a relative jump can continue at, other than the next instruction.
+fn jump_targets(instruction: &Decoded) -> Vec<Option<usize>> {
+    relative_offsets(&instruction.opcode.code, instruction.immediate)
+        .into_iter()
+        .map(|offset| relative_target(instruction.next(), offset))
+        .collect()
+}
+
+fn validate_instructions(
+    index: usize,
+    section: &CodeSection,
+    instructions: &[Decoded],
+    functions: &[FunctionType],
+    container: &Container,
+) -> Result<(), EofError> {
+    let invalid = |offset, reason| {
+        Err(EofError::Instruction {
+            section: index,
+            offset,
+            reason,
+        })
+    };
+    let starts: Vec<usize> = instructions.iter().map(|i| i.offset).collect();
+    let mut returns = false;
+    for instruction in instructions {
+        let offset = instruction.offset;
+        for target in jump_targets(instruction) {
+            if target.is_none_or(|target| starts.binary_search(&target).is_err()) {
+                return invalid(offset, "jump target is not an instruction in the section");
+            }
+        }
+        match instruction.opcode.code {
+            OpCodes::CALLF | OpCodes::JUMPF => {
+                let Some(function) = functions.get(instruction.section_index()) else {
+                    return invalid(offset, "code section does not exist");
+                };
+                if instruction.opcode.code == OpCodes::CALLF && !function.returns() {
+                    return invalid(offset, "CALLF to a section that does not return");
+                }
+                returns |= instruction.opcode.code == OpCodes::JUMPF && function.returns();
+            }
+            OpCodes::RETF => returns = true,
+            OpCodes::DATALOADN => {
+                let data_offset = instruction.section_index();
+                if data_offset + 32 > container.declared_data_size {
+                    return invalid(offset, "DATALOADN reads past the data section");
+                }
+            }
+            _ => {}
+        }
+    }
+    if returns != section.function.returns() {
+        return Err(EofError::Type {
+            section: index,
+            reason: "outputs do not match the RETF and JUMPF instructions",
+        });
+    }
+    match instructions.last() {
+        Some(last) if last.opcode.halts || last.opcode.code == OpCodes::RJUMP => Ok(()),
+        _ => invalid(section.code.len(), "code runs past the end of the section"),
+    }
+}
+
+// Computes the range of stack heights at every instruction in a single pass, as in EIP-5450.
+// Forward jumps widen the range at their target, backward jumps must arrive with exactly the
+// range the target already has.
+fn validate_stack(
+    index: usize,
+    section: &CodeSection,
+    instructions: &[Decoded],
+    functions: &[FunctionType],
+) -> Result<(), EofError> {
+    let invalid = |offset, reason| {
+        Err(EofError::StackHeight {
+            section: index,
+            offset,
+            reason,
+        })
+    };
+    let function = section.function;
+    let mut heights: Vec<Option<(usize, usize)>> = vec![None; section.code.len()];
+    heights[0] = Some((function.inputs as usize, function.inputs as usize));
+    let mut max_height = function.inputs as usize;
+    for instruction in instructions {
+        let offset = instruction.offset;
+        let Some((min, max)) = heights[offset] else {
+            return invalid(offset, "unreachable instruction");
+        };
+        let (inputs, outputs) = stack_effect(instruction.opcode, instruction.immediate, functions);
+        if min < inputs {
+            return invalid(offset, "stack underflow");
+        }
+        match instruction.opcode.code {
+            OpCodes::CALLF | OpCodes::JUMPF => {
+                let target = functions[instruction.section_index()];
+                if max + target.max_stack_height as usize - target.inputs as usize > STACK_LIMIT {
+                    return invalid(offset, "stack overflow in the called section");
+                }
+                // A returning section that is jumped to returns to our caller, so what it
+                // leaves on the stack must add up to our outputs.
+                if instruction.opcode.code == OpCodes::JUMPF && target.returns() {
+                    let expected = (function.outputs as usize + target.inputs as usize)
+                        .checked_sub(target.outputs as usize);
+                    if !function.returns() || expected.is_none_or(|h| (min, max) != (h, h)) {
+                        return invalid(offset, "JUMPF with the wrong number of outputs");
+                    }
+                }
+            }
+            OpCodes::RETF => {
+                let outputs = function.outputs as usize;
+                if (min, max) != (outputs, outputs) {
+                    return invalid(offset, "RETF with the wrong number of outputs");
+                }
+            }
+            _ => {}
+        }
+        let height = (min - inputs + outputs, max - inputs + outputs);
+        max_height = max_height.max(height.1);
+        if height.1 > MAX_STACK_HEIGHT {
+            return invalid(offset, "stack overflow");
+        }
+
+        let mut successors = jump_targets(instruction);
+        if !instruction.opcode.halts && instruction.opcode.code != OpCodes::RJUMP {
+            successors.push(Some(instruction.next()));
+        }
+        // Targets were checked by validate_instructions, and a last instruction that falls
+        // through is rejected there as well.
+        for successor in successors.into_iter().flatten() {
+            let Some(entry) = heights.get_mut(successor) else {
+                continue;
+            };
+            if successor > offset {
+                *entry = Some(match *entry {
+                    Some((min, max)) => (min.min(height.0), max.max(height.1)),
+                    None => height,
+                });
+            } else if *entry != Some(height) {
+                return invalid(offset, "backward jump with a different stack height");
+            }
+        }
+    }
+    if max_height != function.max_stack_height as usize {
+        return invalid(0, "max stack height does not match the type section");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::opcode::Hardfork;
+
+    use super::{Container, EofError, FunctionType};
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        let hex: String = hex.split_whitespace().collect();
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // A container with a single code section, whose type and code are given as hex.
+    fn container(function: &str, code: &str, data: &str) -> Vec<u8> {
+        let code = from_hex(code);
+        let data = from_hex(data);
+        let mut bytes = from_hex("ef0001 010004 020001");
+        bytes.extend_from_slice(&(code.len() as u16).to_be_bytes());
+        bytes.push(0x04);
+        bytes.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        bytes.push(0x00);
+        bytes.extend(from_hex(function));
+        bytes.extend(code);
+        bytes.extend(data);
+        bytes
+    }
+
+    fn validate(bytes: &[u8]) -> Result<Container, EofError> {
+        let container = Container::parse(bytes)?;
+        container.validate(Hardfork::Prague)?;
+        Ok(container)
+    }
+
+    #[test]
+    fn parse_sections() {
+        let bytes = from_hex(
+            "ef0001 010008 0200020005 0001 03000100000014 040002 00
+             00800001 01010001
+             5f e30001 00 e4 <container> beef"
+                .replace(
+                    "<container>",
+                    "ef0001 010004 0200010001 040000 00 00800000 00",
+                )
+                .as_str(),
+        );
+        let container = validate(&bytes).unwrap();
+        assert_eq!(container.code_sections.len(), 2);
+        assert_eq!(container.code_sections[0].offset, 0x20);
+        assert_eq!(
+            container.code_sections[0].code,
+            [0x5f, 0xe3, 0x00, 0x01, 0x00]
+        );
+        assert_eq!(
+            container.code_sections[1].function,
+            FunctionType {
+                inputs: 1,
+                outputs: 1,
+                max_stack_height: 1
+            }
+        );
+        assert_eq!(container.container_sections.len(), 1);
+        assert_eq!(container.data, [0xbe, 0xef]);
+    }
+
+    #[test]
+    fn invalid_headers() {
+        let invalid = |bytes: &[u8]| match Container::parse(bytes) {
+            Err(EofError::Header { reason, .. }) => reason,
+            result => panic!("expected an invalid header, got {:?}", result),
+        };
+        assert_eq!(invalid(&from_hex("ef01")), "missing EOF magic");
+        assert_eq!(invalid(&from_hex("ef0002")), "unsupported version");
+        assert_eq!(
+            invalid(&from_hex("ef0001 010008 0200010001 040000 00 00800000 00")),
+            "type section size does not match the code sections"
+        );
+        assert_eq!(
+            invalid(&from_hex("ef0001 010004 0200010002 040000 00 00800000 00")),
+            "truncated code section"
+        );
+        assert_eq!(
+            invalid(
+                &container("00800000", "00", "")
+                    .into_iter()
+                    .chain([0xff])
+                    .collect::<Vec<_>>()
+            ),
+            "bytes after the data section"
+        );
+        let truncated_data = from_hex("ef0001 010004 0200010001 040002 00 00800000 00 ff");
+        assert!(Container::parse(&truncated_data).is_ok());
+        assert!(validate(&truncated_data).is_err());
+    }
+
+    #[test]
+    fn invalid_instructions() {
+        let reason = |function: &str, code: &str| match validate(&container(function, code, "")) {
+            Err(EofError::Instruction { reason, .. }) => reason,
+            Err(EofError::Type { reason, .. }) => reason,
+            Err(EofError::StackHeight { reason, .. }) => reason,
+            result => panic!("expected invalid code, got {:?}", result),
+        };
+        // JUMP, SELFDESTRUCT and CODECOPY do not exist in EOF.
+        assert_eq!(reason("00800002", "6000 56"), "undefined instruction");
+        assert_eq!(reason("00800000", "61 00"), "truncated immediate");
+        assert_eq!(
+            reason("00800001", "5f 50"),
+            "code runs past the end of the section"
+        );
+        // RJUMP into the immediate of PUSH1.
+        assert_eq!(
+            reason("00800001", "e00001 6000 00"),
+            "jump target is not an instruction in the section"
+        );
+        assert_eq!(
+            reason("00800000", "e30001 00"),
+            "code section does not exist"
+        );
+        assert_eq!(
+            reason("00800000", "e4"),
+            "outputs do not match the RETF and JUMPF instructions"
+        );
+        assert_eq!(
+            reason("00800000", "d10000 00"),
+            "DATALOADN reads past the data section"
+        );
+    }
+
+    #[test]
+    fn stack_heights() {
+        let reason = |code: &str, height: &str| match validate(&container(
+            &format!("0080{}", height),
+            code,
+            "",
+        )) {
+            Err(EofError::StackHeight { reason, .. }) => reason,
+            result => panic!("expected an invalid stack height, got {:?}", result),
+        };
+        assert_eq!(reason("01 00", "0000"), "stack underflow");
+        assert_eq!(reason("00 00", "0000"), "unreachable instruction");
+        assert_eq!(
+            reason("5f 5f 00", "0001"),
+            "max stack height does not match the type section"
+        );
+        // A loop that pushes on every iteration.
+        assert_eq!(
+            reason("5f e0fffc", "0001"),
+            "backward jump with a different stack height"
+        );
+
+        // Both branches of RJUMPI meet at the STOP with one item on the stack.
+        let code = "5f e10004 5f e00001 5f 00";
+        assert!(validate(&container("00800001", code, "")).is_ok());
+        // Forward jumps may arrive with different heights, here 0 and 2.
+        let code = "5f e10002 5f 5f 00";
+        assert!(validate(&container("00800002", code, "")).is_ok());
+        // RJUMPV with two cases, then DUPN, SWAPN and EXCHANGE on three items.
+        let code = "5f 5f 5f e2010001 0002 00 5b 5b e600 e700 e800 00";
+        assert!(validate(&container("00800003", code, "")).is_ok());
+    }
+}
@@ -6,6 +6,7 @@ use crate::{
     instruction::JumpInstruction,
     memory::Memory,
     stack::{Stack, StackElement},
+    storage::Storage,
 };
 
 #[derive(Debug, Clone)]
@@ -18,6 +19,7 @@ pub struct ParsedInstructionSet {
 
     pub stack: Stack,
     pub memory: Memory,
+    pub storage: Storage,
 }
 
 impl LowerHex for ParsedInstructionSet {
@@ -28,14 +30,16 @@ impl LowerHex for ParsedInstructionSet {
 
 #[derive(Clone, Debug)]
 pub struct Flow {
+    start: Hex,
     steps: HashMap<Hex, ParsedInstructionSet>,
 }
 
 impl Flow {
     pub fn new(start: ParsedInstructionSet) -> Flow {
-        let mut steps = HashMap::new();
-        steps.insert(start.start, start);
-        Flow { steps }
+        Flow {
+            start: start.start,
+            steps: HashMap::from([(start.start, start)]),
+        }
     }
 
     pub fn add_step(&mut self, step: ParsedInstructionSet) {
@@ -44,9 +48,9 @@ impl Flow {
 
     // Return the target for which we have no continuation.
     pub fn get_last_step(&self) -> Option<&ParsedInstructionSet> {
-        for (_, step) in &self.steps {
+        for step in self.steps.values() {
             if let Some(target) = &step.target {
-                if self.steps.get(&target.value).is_none() {
+                if !self.steps.contains_key(&target.value) {
                     return Some(step);
                 }
             }
@@ -55,22 +59,84 @@ impl Flow {
     }
 
     pub fn print(&self) {
-        let mut target = Some(StackElement {
-            value: Hex(0),
-            origin: Hex(0),
-            size: 1,
-        });
-        while let Some(t) = target {
-            if let Some(step) = &self.steps.get(&t.value) {
-                info!("step start {:02x}, jumping using {:?}", t.value, step.jump);
-                target = step.target.clone();
+        for line in self.describe() {
+            info!("{}", line);
+        }
+    }
+
+    // One line per step in the order they are executed, ending with the jump that leaves the
+    // flow or returns to a step that was already described.
+    fn describe(&self) -> Vec<String> {
+        let steps = self.steps();
+        let mut lines: Vec<String> = steps
+            .iter()
+            .map(|step| {
+                format!(
+                    "step start {:02x}, jumping using {:?}",
+                    step.start, step.jump
+                )
+            })
+            .collect();
+        if let Some(target) = steps.last().and_then(|step| step.target.as_ref()) {
+            if steps.iter().any(|step| step.start == target.value) {
+                lines.push(format!("step start {:x}, LOOP", target.value));
             } else {
-                info!("step start {:x}, END", t.value,);
+                lines.push(format!("step start {:x}, END", target.value));
+            }
+        }
+        lines
+    }
+    // The steps in the order they are executed, from the first step to the one without a
+    // continuation. A step that is reached again ends the walk, since the steps are kept by
+    // their offset and only the last visit of a loop is known.
+    pub fn steps(&self) -> Vec<&ParsedInstructionSet> {
+        let mut steps: Vec<&ParsedInstructionSet> = Vec::new();
+        let mut next = Some(self.start);
+        while let Some(step) = next.and_then(|start| self.steps.get(&start)) {
+            if steps.iter().any(|visited| visited.start == step.start) {
                 break;
             }
+            steps.push(step);
+            next = step.target.as_ref().map(|target| target.value);
         }
+        steps
     }
+
+    // The jumps taken by the steps of this flow, in no particular order.
+    pub fn jumps(&self) -> impl Iterator<Item = &JumpInstruction> {
+        self.steps.values().filter_map(|step| step.jump.as_ref())
+    }
+
     pub fn len(&self) -> usize {
         self.steps.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{flow_parser::FlowParser, testing::parse};
+
+    #[test]
+    fn describe_looping_flow() {
+        let parser = parse(
+            "
+            PUSH1 loop
+            JUMP
+            loop:
+            JUMPDEST
+            PUSH1 loop
+            JUMP
+            ",
+        );
+        let mut flow_parser = FlowParser::new(parser.get_instructions(), parser.get_bytecode());
+        flow_parser.parse_flows();
+        let flow = &flow_parser.flows()[0];
+        assert_eq!(flow.steps().len(), 2);
+        // The step at 0x3 jumps to itself, which ends the description instead of repeating it.
+        let lines = flow.describe();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("step start 00, "));
+        assert!(lines[1].starts_with("step start 03, "));
+        assert_eq!(lines[2], "step start 3, LOOP");
+    }
+}
@@ -3,27 +3,52 @@ use std::collections::{BTreeMap, HashMap};
 use log::{debug, error, info, warn};
 
 use crate::{
-    flow::{Flow, ParsedInstructionSet},
-    hex::Hex,
-    instruction::{Instruction, InstructionSet, JumpType},
-    memory::Memory,
-    opcode::OpCodes,
-    parser::parse_instruction_set,
+    context::ExecutionContext, eof::Container, flow::Flow, hex::Hex, instruction::Instruction,
+    memory::Memory, parser::parse_instruction_set, storage::Storage,
 };
 
 pub struct FlowParser<'a> {
     instructions: &'a HashMap<Hex, Instruction>,
+    code: &'a [u8],
+    // The EOF container the code was parsed into, or None for legacy code.
+    container: Option<&'a Container>,
+    context: ExecutionContext,
+    storage: Storage,
+    // The offset execution starts at, which for EOF is the first code section.
+    start: Hex,
     flows: Vec<Flow>,
 }
 
-impl FlowParser<'_> {
-    pub fn new(instructions: &HashMap<Hex, Instruction>) -> FlowParser {
+impl<'a> FlowParser<'a> {
+    pub fn new(instructions: &'a HashMap<Hex, Instruction>, code: &'a [u8]) -> FlowParser<'a> {
         FlowParser {
             instructions,
+            code,
+            container: None,
+            context: ExecutionContext::new(),
+            storage: Storage::new(),
+            start: Hex::ZERO,
             flows: Vec::new(),
         }
     }
 
+    // Sets the transaction every flow is executed in.
+    pub fn set_context(&mut self, context: ExecutionContext) {
+        self.context = context;
+    }
+
+    // Sets the storage every flow starts from, for example the state of a deployed contract.
+    pub fn set_storage(&mut self, storage: Storage) {
+        self.storage = storage;
+    }
+
+    // Sets the EOF container the code was parsed into. Execution starts at its first code
+    // section, and the data section instructions read from it.
+    pub fn set_container(&mut self, container: &'a Container) {
+        self.start = Hex::from(container.code_sections[0].offset);
+        self.container = Some(container);
+    }
+
     // Iterate over all instruction sets and reconstruct all jumps.
     pub fn parse_flows(&mut self) {
         info!("parsing flows");
@@ -32,12 +57,21 @@ impl FlowParser<'_> {
             debug!("{:?}", instruction);
         }
         let memory = Memory::new();
-        let steps =
-            parse_instruction_set(Hex::from(0), &self.instructions, None, memory.clone(), None);
+        let steps = parse_instruction_set(
+            self.start,
+            self.instructions,
+            self.code,
+            self.container,
+            &self.context,
+            None,
+            memory.clone(),
+            self.storage.clone(),
+            None,
+        );
         for step in &steps {
             debug!("first step: {:x}", step);
             // Update the stack for this section
-            println!("Parsing jump {:?}", step.jump);
+            debug!("Parsing jump {:?}", step.jump);
 
             let flow = Flow::new(step.clone());
             let flows = self.parse_next_step(flow);
@@ -54,25 +88,29 @@ impl FlowParser<'_> {
             last_step.target, last_step.jump, last_step.stack
         );
             if let Some(ref target) = last_step.target {
-                if let Some(next_step) = self.instructions.get(&target.value) {
+                let destination = last_step
+                    .jump
+                    .as_ref()
+                    .and_then(|jump| jump.destination(self.instructions));
+                if let Some(next_step) = destination {
                     warn!("Our next step starts at {0:?}", next_step.index);
-                    if next_step.opcode.code != OpCodes::JUMPDEST {
-                        flow.print();
-                        panic!("next_step does not start with JUMPDEST");
-                    }
-                    println!("{:?}", next_step);
+                    debug!("{:?}", next_step);
                     let targets = parse_instruction_set(
-                        next_step.index.clone(),
-                        &self.instructions,
+                        next_step.index,
+                        self.instructions,
+                        self.code,
+                        self.container,
+                        &self.context,
                         Some(last_step.stack.clone()),
                         last_step.memory.clone(),
+                        last_step.storage.clone(),
                         None,
                     );
                     // Now, we want to reparse the next step, so we can update its stack from the
                     // 'leftovers' from the last step.
                     // Now we need to append our flow with the new step. Clone the flow for every jump
                     // found in the new_step.
-                    if targets.len() == 0 {
+                    if targets.is_empty() {
                         warn!(
                             "Section {} does not have any defined targets.",
                             next_step.index
@@ -86,12 +124,17 @@ impl FlowParser<'_> {
                             result.append(&mut self.parse_next_step(flow.clone()));
                         } else {
                             error!("Flow got too long...");
+                            result.push(flow.clone());
                         }
                     }
                 } else {
-                    flow.print();
-                    println!("{:?}", last_step);
-                    panic!("Cannot parse next step; {:?} does not exist as an individual instruction set.", target.value);
+                    // Jumping to a symbolic target or to anything but a JUMPDEST halts
+                    // exceptionally, which ends the flow.
+                    warn!(
+                        "Flow ends at a jump to {:?}, which is not a jump destination",
+                        target
+                    );
+                    debug!("{:?}", last_step);
                 }
             } else {
                 panic!("Reached end of flow, there is no target to jump to.");
@@ -99,7 +142,11 @@ impl FlowParser<'_> {
         } else {
             debug!("flow ends");
         }
-        result.push(flow);
+        // Only flows that could not be continued any further are complete; the branches found
+        // above already contain this flow as their prefix.
+        if result.is_empty() {
+            result.push(flow);
+        }
         result
     }
 
@@ -111,11 +158,15 @@ impl FlowParser<'_> {
 mod tests {
     use crate::{
         flow_parser::FlowParser,
-        opcode::OpCodes::{
-            CALLVALUE as OPCODE_CALLVALUE, DUP1, ISZERO, JUMP, JUMPDEST, JUMPI, MSTORE, PUSH1,
-            PUSH2, REVERT, STOP,
+        opcode::{
+            Hardfork,
+            OpCodes::{
+                CALLVALUE as OPCODE_CALLVALUE, DUP1, ISZERO, JUMP, JUMPDEST, JUMPI, MSTORE, PUSH1,
+                PUSH2, REVERT, STOP,
+            },
         },
         parser::Parser,
+        testing::parse,
     };
     use test_log::test;
 
@@ -137,12 +188,12 @@ mod tests {
             STOP as u32,     //0xc
         ]);
 
-        let parser = Parser::new(input);
+        let parser = Parser::new(input, Hardfork::Cancun).unwrap();
         let instruction_sets = parser.get_instruction_sets();
         let instructions = parser.get_instructions();
         assert_eq!(instruction_sets.len(), 4);
 
-        let mut flow_parser = FlowParser::new(instructions);
+        let mut flow_parser = FlowParser::new(instructions, parser.get_bytecode());
         flow_parser.parse_flows();
         let flows = flow_parser.flows;
         assert_eq!(flows.len(), 1);
@@ -158,12 +209,12 @@ mod tests {
             STOP as u32,     // 0x4
         ]);
 
-        let parser = Parser::new(input);
+        let parser = Parser::new(input, Hardfork::Cancun).unwrap();
         let instruction_sets = parser.get_instruction_sets();
         let instructions = parser.get_instructions();
         // we have two sections; one before the jump and one after the jump. From JUMPDEST to STOP.
         assert_eq!(instruction_sets.len(), 2);
-        let mut flow_parser = FlowParser::new(instructions);
+        let mut flow_parser = FlowParser::new(instructions, parser.get_bytecode());
         flow_parser.parse_flows();
 
         let flow = flow_parser.flows;
@@ -189,11 +240,11 @@ mod tests {
             JUMPDEST as u32, //0xe
             STOP as u32,     //0xf
         ]);
-        let parser = Parser::new(input);
+        let parser = Parser::new(input, Hardfork::Cancun).unwrap();
         let instructions = parser.get_instructions();
         let instruction_sets = parser.get_instruction_sets();
         assert_eq!(instruction_sets.len(), 4, "{:?}", instruction_sets);
-        let mut flow_parser = FlowParser::new(instructions);
+        let mut flow_parser = FlowParser::new(instructions, parser.get_bytecode());
         flow_parser.parse_flows();
         let flows = flow_parser.flows;
         println!("flows: {:?}", flows);
@@ -214,25 +265,52 @@ mod tests {
             DUP1 as u32,
             ISZERO as u32,
             PUSH2 as u32,
-            00 as u32,
-            10 as u32,
+            0x00,
+            0x10,
             JUMPI as u32,
             PUSH1 as u32,
-            00 as u32,
+            0x00,
             DUP1 as u32,
             REVERT as u32,
             JUMPDEST as u32,
             REVERT as u32,
         ]);
 
-        let parser = Parser::new(input);
+        let parser = Parser::new(input, Hardfork::Cancun).unwrap();
         let instruction_sets = parser.get_instruction_sets();
         let instructions = parser.get_instructions();
         assert_eq!(instruction_sets.len(), 2);
 
-        let mut flow_parser = FlowParser::new(instructions);
+        let mut flow_parser = FlowParser::new(instructions, parser.get_bytecode());
         flow_parser.parse_flows();
         let flows = flow_parser.flows;
         assert_eq!(flows.len(), 2);
     }
+
+    #[test]
+    fn parse_conditional_flow_from_assembly() {
+        let parser = parse(
+            "
+            PUSH end
+            PUSH 0x1
+            PUSH taken
+            JUMPI
+            PUSH1 0x00
+            DUP1
+            REVERT
+            taken:
+            JUMPDEST
+            JUMP
+            end:
+            JUMPDEST
+            STOP
+            ",
+        );
+        let mut flow_parser = FlowParser::new(parser.get_instructions(), parser.get_bytecode());
+        flow_parser.parse_flows();
+        // Both sides of the JUMPI are followed, whatever the condition.
+        let mut lengths: Vec<usize> = flow_parser.flows.iter().map(|flow| flow.len()).collect();
+        lengths.sort();
+        assert_eq!(lengths, [1, 3]);
+    }
 }
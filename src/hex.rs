@@ -1,44 +1,374 @@
 use core::fmt;
 use std::{
-    num::{ParseIntError, Wrapping},
-    ops::{Add, AddAssign, BitAnd, Index, Mul, Range, Rem, Shl, Shr, Sub},
+    cmp::Ordering,
+    ops::{Add, AddAssign, BitAnd, BitOr, BitXor, Div, Mul, Neg, Not, Rem, Shl, Shr, Sub},
 };
 
-#[derive(Clone, Default, PartialEq, Copy, PartialOrd, Hash, Eq, Ord)]
-pub struct Hex(pub u128);
+// A 256-bit unsigned EVM word. All arithmetic wraps modulo 2^256, just like the EVM does.
+//
+// The value is stored as four 64-bit limbs, least significant limb first.
+#[derive(Clone, Default, PartialEq, Copy, Hash, Eq)]
+pub struct Hex([u64; 4]);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseHexError(String);
+
+impl fmt::Display for ParseHexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseHexError {}
+
+impl Hex {
+    pub const ZERO: Hex = Hex([0; 4]);
+    pub const ONE: Hex = Hex([1, 0, 0, 0]);
+    pub const MAX: Hex = Hex([u64::MAX; 4]);
+
+    pub const fn new(value: u128) -> Hex {
+        Hex([value as u64, (value >> 64) as u64, 0, 0])
+    }
+
+    // Builds a word from big-endian bytes. Inputs shorter than 32 bytes are treated as the
+    // least significant bytes; only the last 32 bytes of longer inputs are used.
+    pub fn from_be_bytes(bytes: &[u8]) -> Hex {
+        let mut result = Hex::ZERO;
+        let start = bytes.len().saturating_sub(32);
+        for byte in &bytes[start..] {
+            result = (result << 8) | Hex::from(*byte);
+        }
+        result
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        for (i, limb) in self.0.iter().enumerate() {
+            let bytes = limb.to_be_bytes();
+            result[(3 - i) * 8..(4 - i) * 8].copy_from_slice(&bytes);
+        }
+        result
+    }
+
+    pub fn from_str_radix(value: &str, radix: u32) -> Result<Hex, ParseHexError> {
+        if value.is_empty() {
+            return Err(ParseHexError(
+                "cannot parse a word from an empty string".to_string(),
+            ));
+        }
+        let mut result = Hex::ZERO;
+        for char in value.chars() {
+            let digit = char
+                .to_digit(radix)
+                .ok_or_else(|| ParseHexError(format!("invalid digit '{}' in {}", char, value)))?;
+            let (shifted, overflow) = result.overflowing_mul(Hex::from(radix));
+            let (next, carry) = shifted.overflowing_add(Hex::from(digit));
+            if overflow || carry {
+                return Err(ParseHexError(format!("{} does not fit in 256 bits", value)));
+            }
+            result = next;
+        }
+        Ok(result)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Hex::ZERO
+    }
+
+    // Number of significant bits in the word.
+    pub fn bits(&self) -> usize {
+        for i in (0..4).rev() {
+            if self.0[i] != 0 {
+                return i * 64 + (64 - self.0[i].leading_zeros() as usize);
+            }
+        }
+        0
+    }
+
+    // Number of significant bytes in the word, which is the size of the smallest PUSH that
+    // could have pushed it.
+    pub fn byte_len(&self) -> usize {
+        self.bits().div_ceil(8)
+    }
+
+    pub fn bit(&self, index: usize) -> bool {
+        index < 256 && (self.0[index / 64] >> (index % 64)) & 1 == 1
+    }
+
+    // Converts the word to a `usize`, panicking if it does not fit.
+    pub fn as_usize(&self) -> usize {
+        usize::try_from(*self).expect("Hex value does not fit in a usize")
+    }
+
+    pub fn overflowing_add(self, rhs: Hex) -> (Hex, bool) {
+        let mut result = [0u64; 4];
+        let mut carry = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (sum, c1) = self.0[i].overflowing_add(rhs.0[i]);
+            let (sum, c2) = sum.overflowing_add(carry as u64);
+            *limb = sum;
+            carry = c1 || c2;
+        }
+        (Hex(result), carry)
+    }
+
+    pub fn overflowing_sub(self, rhs: Hex) -> (Hex, bool) {
+        let mut result = [0u64; 4];
+        let mut borrow = false;
+        for (i, limb) in result.iter_mut().enumerate() {
+            let (diff, b1) = self.0[i].overflowing_sub(rhs.0[i]);
+            let (diff, b2) = diff.overflowing_sub(borrow as u64);
+            *limb = diff;
+            borrow = b1 || b2;
+        }
+        (Hex(result), borrow)
+    }
+
+    pub fn overflowing_mul(self, rhs: Hex) -> (Hex, bool) {
+        let result = self.full_mul(rhs);
+        let overflow = result[4..].iter().any(|limb| *limb != 0);
+        (Hex([result[0], result[1], result[2], result[3]]), overflow)
+    }
+
+    // The full 512-bit product of two words, least significant limb first.
+    fn full_mul(self, rhs: Hex) -> [u64; 8] {
+        let mut result = [0u64; 8];
+        for i in 0..4 {
+            let mut carry: u128 = 0;
+            for j in 0..4 {
+                let product = self.0[i] as u128 * rhs.0[j] as u128 + result[i + j] as u128 + carry;
+                result[i + j] = product as u64;
+                carry = product >> 64;
+            }
+            result[i + 4] = carry as u64;
+        }
+        result
+    }
+
+    // Reduces a wide number, given as limbs with the least significant limb first, modulo
+    // `modulus`. The modulus must not be zero.
+    fn wide_rem(limbs: &[u64], modulus: Hex) -> Hex {
+        let mut remainder = Hex::ZERO;
+        for i in (0..limbs.len() * 64).rev() {
+            // The remainder stays below the modulus, so at most one bit is shifted out here.
+            let carry = remainder.is_negative();
+            remainder = remainder << 1;
+            remainder.0[0] |= (limbs[i / 64] >> (i % 64)) & 1;
+            if carry || remainder >= modulus {
+                remainder = remainder - modulus;
+            }
+        }
+        remainder
+    }
+
+    // `(self + rhs) % modulus` without the intermediate sum wrapping, as ADDMOD specifies. A
+    // zero modulus yields zero.
+    pub fn addmod(self, rhs: Hex, modulus: Hex) -> Hex {
+        if modulus.is_zero() {
+            return Hex::ZERO;
+        }
+        let (sum, carry) = self.overflowing_add(rhs);
+        let Hex(limbs) = sum;
+        Hex::wide_rem(
+            &[limbs[0], limbs[1], limbs[2], limbs[3], carry as u64],
+            modulus,
+        )
+    }
+
+    // `(self * rhs) % modulus` computed over the full 512-bit product, as MULMOD specifies.
+    // A zero modulus yields zero.
+    pub fn mulmod(self, rhs: Hex, modulus: Hex) -> Hex {
+        if modulus.is_zero() {
+            return Hex::ZERO;
+        }
+        Hex::wide_rem(&self.full_mul(rhs), modulus)
+    }
+
+    // Unsigned division returning quotient and remainder. Division by zero yields zero for
+    // both, as the EVM specifies for DIV and MOD.
+    pub fn div_rem(self, rhs: Hex) -> (Hex, Hex) {
+        if rhs.is_zero() {
+            return (Hex::ZERO, Hex::ZERO);
+        }
+        if self < rhs {
+            return (Hex::ZERO, self);
+        }
+        let mut quotient = Hex::ZERO;
+        let mut remainder = Hex::ZERO;
+        for i in (0..self.bits()).rev() {
+            remainder = remainder << 1;
+            if self.bit(i) {
+                remainder.0[0] |= 1;
+            }
+            if remainder >= rhs {
+                remainder = remainder - rhs;
+                quotient.0[i / 64] |= 1 << (i % 64);
+            }
+        }
+        (quotient, remainder)
+    }
+
+    // Wrapping exponentiation, as performed by the EXP opcode.
+    pub fn pow(self, exponent: Hex) -> Hex {
+        let mut result = Hex::ONE;
+        let mut base = self;
+        for i in 0..exponent.bits() {
+            if exponent.bit(i) {
+                result = result * base;
+            }
+            base = base * base;
+        }
+        result
+    }
+
+    // Whether the word is negative when read as a two's complement signed integer.
+    pub fn is_negative(&self) -> bool {
+        self.bit(255)
+    }
+
+    // The absolute value of the word when read as a signed integer. The most negative value
+    // has no positive counterpart and is returned unchanged, which matches its unsigned value.
+    fn unsigned_abs(self) -> Hex {
+        if self.is_negative() {
+            -self
+        } else {
+            self
+        }
+    }
+
+    // Signed division (SDIV). The quotient is rounded towards zero, division by zero yields
+    // zero and the most negative value divided by -1 overflows back to itself.
+    pub fn sdiv(self, rhs: Hex) -> Hex {
+        let quotient = self.unsigned_abs() / rhs.unsigned_abs();
+        if self.is_negative() != rhs.is_negative() {
+            -quotient
+        } else {
+            quotient
+        }
+    }
+
+    // Signed remainder (SMOD). The result takes the sign of the dividend; a zero divisor
+    // yields zero.
+    pub fn smod(self, rhs: Hex) -> Hex {
+        let remainder = self.unsigned_abs() % rhs.unsigned_abs();
+        if self.is_negative() {
+            -remainder
+        } else {
+            remainder
+        }
+    }
+
+    // Compares two words as two's complement signed integers.
+    pub fn signed_cmp(&self, other: &Hex) -> Ordering {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => self.cmp(other),
+        }
+    }
+
+    // Arithmetic shift right (SAR); the sign bit is shifted in from the left.
+    pub fn sar(self, shift: Hex) -> Hex {
+        if self.is_negative() {
+            !(!self >> shift)
+        } else {
+            self >> shift
+        }
+    }
+
+    // Sign-extends the value from the byte at `byte` (counted from the least significant
+    // byte), as SIGNEXTEND does. Indices of 31 and above leave the value untouched.
+    pub fn signextend(self, byte: Hex) -> Hex {
+        if byte >= Hex::new(31) {
+            return self;
+        }
+        let sign_bit = byte.as_usize() * 8 + 7;
+        let mask = Hex::MAX << (sign_bit + 1);
+        if self.bit(sign_bit) {
+            self | mask
+        } else {
+            self & !mask
+        }
+    }
+}
+
 impl AddAssign for Hex {
     fn add_assign(&mut self, rhs: Self) {
-        self.0 += rhs.0;
+        *self = *self + rhs;
     }
 }
 impl Add for Hex {
     type Output = Hex;
 
     fn add(self, rhs: Self) -> Self::Output {
-        Hex(self.0 + rhs.0)
+        self.overflowing_add(rhs).0
     }
 }
 impl Mul for Hex {
     type Output = Hex;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        Hex(self.0 * rhs.0)
+        self.overflowing_mul(rhs).0
+    }
+}
+impl Div for Hex {
+    type Output = Hex;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        self.div_rem(rhs).0
+    }
+}
+
+impl Shl<usize> for Hex {
+    type Output = Self;
+
+    fn shl(self, shift: usize) -> Self::Output {
+        if shift >= 256 {
+            return Hex::ZERO;
+        }
+        let limbs = shift / 64;
+        let bits = shift % 64;
+        let mut result = [0u64; 4];
+        for (i, limb) in result.iter_mut().enumerate().skip(limbs) {
+            *limb = self.0[i - limbs] << bits;
+            if bits > 0 && i > limbs {
+                *limb |= self.0[i - limbs - 1] >> (64 - bits);
+            }
+        }
+        Hex(result)
+    }
+}
+impl Shr<usize> for Hex {
+    type Output = Self;
+
+    fn shr(self, shift: usize) -> Self::Output {
+        if shift >= 256 {
+            return Hex::ZERO;
+        }
+        let limbs = shift / 64;
+        let bits = shift % 64;
+        let mut result = [0u64; 4];
+        for (i, limb) in result.iter_mut().enumerate().take(4 - limbs) {
+            *limb = self.0[i + limbs] >> bits;
+            if bits > 0 && i + limbs + 1 < 4 {
+                *limb |= self.0[i + limbs + 1] << (64 - bits);
+            }
+        }
+        Hex(result)
     }
 }
 impl Shl<Hex> for Hex {
     type Output = Self;
 
     fn shl(self, shift: Hex) -> Self::Output {
-        let wrapped_self = Wrapping(self.0);
-        Self(wrapped_self.shl(shift.0 as usize).0)
+        self << usize::try_from(shift).unwrap_or(usize::MAX)
     }
 }
 impl Shr<Hex> for Hex {
     type Output = Self;
 
     fn shr(self, shift: Hex) -> Self::Output {
-        let wrapped_self = Wrapping(self.0);
-        Self(wrapped_self.shr(shift.0 as usize).0)
+        self >> usize::try_from(shift).unwrap_or(usize::MAX)
     }
 }
 
@@ -47,7 +377,39 @@ impl BitAnd for Hex {
 
     fn bitand(self, rhs: Self) -> Self::Output {
         let Self(lhs) = self;
-        Self(lhs & rhs.0)
+        Self(std::array::from_fn(|i| lhs[i] & rhs.0[i]))
+    }
+}
+impl BitOr for Hex {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        let Self(lhs) = self;
+        Self(std::array::from_fn(|i| lhs[i] | rhs.0[i]))
+    }
+}
+impl BitXor for Hex {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self::Output {
+        let Self(lhs) = self;
+        Self(std::array::from_fn(|i| lhs[i] ^ rhs.0[i]))
+    }
+}
+impl Neg for Hex {
+    type Output = Self;
+
+    // Two's complement negation, wrapping the most negative value onto itself.
+    fn neg(self) -> Self::Output {
+        !self + Hex::ONE
+    }
+}
+impl Not for Hex {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        let Self(value) = self;
+        Self(value.map(|limb| !limb))
     }
 }
 
@@ -55,8 +417,7 @@ impl Sub for Hex {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        let wrapped_self = Wrapping(self.0);
-        Self(wrapped_self.sub(Wrapping(rhs.0)).0)
+        self.overflowing_sub(rhs).0
     }
 }
 
@@ -64,47 +425,93 @@ impl Rem for Hex {
     type Output = Self;
 
     fn rem(self, rhs: Self) -> Self::Output {
-        let Self(lhs) = self;
-        Self(lhs % rhs.0)
+        self.div_rem(rhs).1
     }
 }
 
+impl Ord for Hex {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+impl PartialOrd for Hex {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<u8> for Hex {
+    fn from(value: u8) -> Self {
+        Hex::new(value as u128)
+    }
+}
 impl From<usize> for Hex {
     fn from(value: usize) -> Self {
-        Hex(value as u128)
+        Hex::new(value as u128)
     }
 }
 impl From<i32> for Hex {
     fn from(value: i32) -> Self {
-        Hex(value as u128)
+        // Negative values are sign-extended over the full word.
+        if value < 0 {
+            !Hex::new(!(value as i128) as u128)
+        } else {
+            Hex::new(value as u128)
+        }
     }
 }
 impl From<u32> for Hex {
     fn from(value: u32) -> Self {
-        Hex(value as u128)
+        Hex::new(value as u128)
+    }
+}
+impl From<u64> for Hex {
+    fn from(value: u64) -> Self {
+        Hex::new(value as u128)
+    }
+}
+impl From<u128> for Hex {
+    fn from(value: u128) -> Self {
+        Hex::new(value)
     }
 }
 
-impl TryFrom<&String> for Hex {
-    type Error = ParseIntError;
+impl TryFrom<Hex> for usize {
+    type Error = ();
 
-    fn try_from(value: &String) -> Result<Self, Self::Error> {
-        let mut result = Hex::from(0);
-        let mut value: String = value.chars().rev().collect();
-        while let Some(value) = value.pop() {
-            let hex = Hex(value.to_digit(16).unwrap().into());
-            result = result << Hex::from(4);
-            result += hex;
+    fn try_from(value: Hex) -> Result<Self, Self::Error> {
+        if value.bits() > usize::BITS as usize {
+            Err(())
+        } else {
+            Ok(value.0[0] as usize)
         }
+    }
+}
 
-        Ok(result)
+impl TryFrom<&String> for Hex {
+    type Error = ParseHexError;
+
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
+        Hex::from_str_radix(value, 16)
     }
 }
 
 impl fmt::LowerHex for Hex {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let val = &self.0;
-        fmt::LowerHex::fmt(&val, f)
+        let mut digits = String::new();
+        for limb in self.0.iter().rev() {
+            if digits.is_empty() {
+                if *limb != 0 {
+                    digits = format!("{:x}", limb);
+                }
+            } else {
+                digits.push_str(&format!("{:016x}", limb));
+            }
+        }
+        if digits.is_empty() {
+            digits.push('0');
+        }
+        f.pad_integral(true, "0x", &digits)
     }
 }
 
@@ -120,3 +527,173 @@ impl fmt::Display for Hex {
         write!(f, "{:04x}", self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::Hex;
+
+    #[test]
+    fn add_wraps_around() {
+        assert_eq!(Hex::MAX + Hex::ONE, Hex::ZERO);
+        assert_eq!(
+            Hex::new(u128::MAX) + Hex::ONE,
+            Hex::from_str_radix("100000000000000000000000000000000", 16).unwrap()
+        );
+    }
+
+    #[test]
+    fn sub_wraps_around() {
+        assert_eq!(Hex::ZERO - Hex::ONE, Hex::MAX);
+        assert_eq!(Hex::new(0x10) - Hex::new(0x1), Hex::new(0xf));
+    }
+
+    #[test]
+    fn mul_wraps_around() {
+        let value = Hex::ONE << 255;
+        assert_eq!(value * Hex::new(2), Hex::ZERO);
+        assert_eq!(Hex::MAX * Hex::MAX, Hex::ONE);
+        assert_eq!(
+            Hex::new(u128::MAX) * Hex::new(u128::MAX),
+            Hex::from_str_radix(
+                "fffffffffffffffffffffffffffffffe00000000000000000000000000000001",
+                16
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn div_and_rem() {
+        assert_eq!(Hex::new(100) / Hex::new(7), Hex::new(14));
+        assert_eq!(Hex::new(100) % Hex::new(7), Hex::new(2));
+        assert_eq!(Hex::MAX / Hex::ONE, Hex::MAX);
+        assert_eq!(Hex::MAX / Hex::MAX, Hex::ONE);
+        assert_eq!(Hex::MAX % Hex::new(0x10), Hex::new(0xf));
+    }
+
+    #[test]
+    fn div_by_zero_is_zero() {
+        assert_eq!(Hex::new(100) / Hex::ZERO, Hex::ZERO);
+        assert_eq!(Hex::new(100) % Hex::ZERO, Hex::ZERO);
+    }
+
+    #[test]
+    fn addmod_uses_wide_intermediate() {
+        assert_eq!(Hex::new(10).addmod(Hex::new(10), Hex::new(8)), Hex::new(4));
+        assert_eq!(Hex::MAX.addmod(Hex::new(2), Hex::new(2)), Hex::ONE);
+        assert_eq!(Hex::MAX.addmod(Hex::MAX, Hex::MAX), Hex::ZERO);
+        assert_eq!(Hex::new(10).addmod(Hex::new(10), Hex::ZERO), Hex::ZERO);
+    }
+
+    #[test]
+    fn mulmod_uses_wide_intermediate() {
+        assert_eq!(Hex::new(10).mulmod(Hex::new(10), Hex::new(8)), Hex::new(4));
+        assert_eq!(Hex::MAX.mulmod(Hex::MAX, Hex::new(12)), Hex::new(9));
+        assert_eq!(Hex::MAX.mulmod(Hex::MAX, Hex::MAX), Hex::ZERO);
+        assert_eq!((Hex::ONE << 255).mulmod(Hex::new(2), Hex::MAX), Hex::ONE);
+        assert_eq!(Hex::new(10).mulmod(Hex::new(10), Hex::ZERO), Hex::ZERO);
+    }
+
+    #[test]
+    fn pow_wraps_around() {
+        assert_eq!(Hex::new(2).pow(Hex::new(10)), Hex::new(1024));
+        assert_eq!(Hex::new(2).pow(Hex::new(256)), Hex::ZERO);
+        assert_eq!(Hex::new(0).pow(Hex::new(0)), Hex::ONE);
+        assert_eq!(Hex::new(2).pow(Hex::new(255)), Hex::ONE << 255);
+    }
+
+    #[test]
+    fn shifts() {
+        assert_eq!(Hex::ONE << 64, Hex::new(1 << 64));
+        assert_eq!(Hex::MAX << 256, Hex::ZERO);
+        assert_eq!(Hex::MAX >> 255, Hex::ONE);
+        assert_eq!((Hex::ONE << 200) >> Hex::new(199), Hex::new(2));
+        assert_eq!(Hex::MAX >> Hex::MAX, Hex::ZERO);
+    }
+
+    #[test]
+    fn ordering_uses_most_significant_limb() {
+        assert!(Hex::ONE << 128 > Hex::new(u128::MAX));
+        assert!(Hex::ZERO < Hex::ONE);
+        assert!(Hex::MAX > Hex::ONE << 255);
+    }
+
+    #[test]
+    fn parse_full_word() {
+        let value = Hex::try_from(&"f".repeat(64)).unwrap();
+        assert_eq!(value, Hex::MAX);
+        assert!(Hex::try_from(&"f".repeat(65)).is_err());
+        assert!(Hex::try_from(&"xyz".to_string()).is_err());
+        assert_eq!(Hex::from_str_radix("256", 10).unwrap(), Hex::new(0x100));
+    }
+
+    #[test]
+    fn be_bytes_round_trip() {
+        let bytes: Vec<u8> = (1..=32).collect();
+        let value = Hex::from_be_bytes(&bytes);
+        assert_eq!(value.to_be_bytes().to_vec(), bytes);
+        assert_eq!(Hex::from_be_bytes(&[0xab, 0xcd]), Hex::new(0xabcd));
+    }
+
+    #[test]
+    fn format_lower_hex() {
+        assert_eq!(format!("{:x}", Hex::ZERO), "0");
+        assert_eq!(format!("{}", Hex::new(0xab)), "00ab");
+        assert_eq!(
+            format!("{:x}", Hex::ONE << 128),
+            format!("1{}", "0".repeat(32))
+        );
+        assert_eq!(format!("{:x}", Hex::MAX), "f".repeat(64));
+    }
+
+    #[test]
+    fn signed_division() {
+        assert_eq!(Hex::from(-10).sdiv(Hex::new(3)), Hex::from(-3));
+        assert_eq!(Hex::new(10).sdiv(Hex::from(-3)), Hex::from(-3));
+        assert_eq!(Hex::from(-10).sdiv(Hex::from(-3)), Hex::new(3));
+        assert_eq!(Hex::from(-10).sdiv(Hex::ZERO), Hex::ZERO);
+        let min = Hex::ONE << 255;
+        assert_eq!(min.sdiv(Hex::from(-1)), min);
+    }
+
+    #[test]
+    fn signed_modulo() {
+        assert_eq!(Hex::from(-10).smod(Hex::new(3)), Hex::from(-1));
+        assert_eq!(Hex::new(10).smod(Hex::from(-3)), Hex::new(1));
+        assert_eq!(Hex::from(-10).smod(Hex::ZERO), Hex::ZERO);
+    }
+
+    #[test]
+    fn signed_comparison() {
+        assert_eq!(Hex::from(-1).signed_cmp(&Hex::ONE), Ordering::Less);
+        assert_eq!(Hex::ONE.signed_cmp(&Hex::from(-1)), Ordering::Greater);
+        assert_eq!(Hex::from(-2).signed_cmp(&Hex::from(-1)), Ordering::Less);
+        assert_eq!(Hex::new(5).signed_cmp(&Hex::new(5)), Ordering::Equal);
+    }
+
+    #[test]
+    fn arithmetic_shift_right() {
+        assert_eq!(Hex::from(-16).sar(Hex::new(2)), Hex::from(-4));
+        assert_eq!(Hex::from(-1).sar(Hex::new(300)), Hex::MAX);
+        assert_eq!(Hex::new(16).sar(Hex::new(2)), Hex::new(4));
+        assert_eq!(Hex::new(16).sar(Hex::new(300)), Hex::ZERO);
+    }
+
+    #[test]
+    fn sign_extension() {
+        assert_eq!(Hex::new(0xff).signextend(Hex::ZERO), Hex::MAX);
+        assert_eq!(Hex::new(0x7f).signextend(Hex::ZERO), Hex::new(0x7f));
+        assert_eq!(Hex::new(0x12ff).signextend(Hex::ZERO), Hex::MAX);
+        assert_eq!(Hex::new(0x8000).signextend(Hex::ONE), Hex::from(-0x8000));
+        assert_eq!(Hex::new(0xff).signextend(Hex::new(31)), Hex::new(0xff));
+        assert_eq!(Hex::new(0xff).signextend(Hex::MAX), Hex::new(0xff));
+    }
+
+    #[test]
+    fn negative_i32_is_sign_extended() {
+        assert_eq!(Hex::from(-1), Hex::MAX);
+        assert_eq!(Hex::from(-2), Hex::MAX - Hex::ONE);
+    }
+}
@@ -1,12 +1,17 @@
 use core::fmt;
-use std::num;
+use log::debug;
+use num_traits::ToPrimitive;
+use std::{cmp::Ordering, collections::HashMap};
 
 use crate::{
+    context::ExecutionContext,
+    eof::{self, Container},
     hex::Hex,
-    memory::Memory,
+    keccak::keccak256,
+    memory::{ByteSource, Memory, MemoryElement, MAX_MEMORY_SIZE},
     opcode::{OpCode, OpCodeResult, OpCodes},
-    stack::{Stack, StackElement},
-    CALLDATA, CALLVALUE,
+    stack::{Stack, StackElement, Symbol},
+    storage::Storage,
 };
 
 #[derive(Clone, Default, PartialEq, Eq, Hash)]
@@ -14,8 +19,17 @@ pub struct Instruction {
     pub args: Vec<Hex>,
     pub opcode: OpCode,
     pub index: Hex,
+    // Every byte that is not an opcode decodes as INVALID, so for INVALID instructions the
+    // original byte is kept here.
+    pub raw_byte: Option<u8>,
 }
 impl Instruction {
+    // The byte this instruction was decoded from.
+    pub fn opcode_byte(&self) -> u8 {
+        self.raw_byte
+            .unwrap_or_else(|| self.opcode.code.to_u8().unwrap_or_default())
+    }
+
     fn stop(&self, _stack: &mut Stack) -> Result<OpCodeResult, ()> {
         Ok(OpCodeResult::End)
     }
@@ -30,6 +44,7 @@ impl Instruction {
             value: left.value + right.value,
             origin: self.index,
             size: 1,
+            symbol: None,
         });
         Ok(OpCodeResult::Ok)
     }
@@ -40,20 +55,127 @@ impl Instruction {
             value: left.value - right.value,
             origin: self.index,
             size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn div(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let left = stack.pop().ok_or(())?;
+        let right = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: left.value / right.value,
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn modulo(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let left = stack.pop().ok_or(())?;
+        let right = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: left.value % right.value,
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn addmod(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let left = stack.pop().ok_or(())?;
+        let right = stack.pop().ok_or(())?;
+        let modulus = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: left.value.addmod(right.value, modulus.value),
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn mulmod(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let left = stack.pop().ok_or(())?;
+        let right = stack.pop().ok_or(())?;
+        let modulus = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: left.value.mulmod(right.value, modulus.value),
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn exp(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let base = stack.pop().ok_or(())?;
+        let exponent = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: base.value.pow(exponent.value),
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn sdiv(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let left = stack.pop().ok_or(())?;
+        let right = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: left.value.sdiv(right.value),
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn smod(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let left = stack.pop().ok_or(())?;
+        let right = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: left.value.smod(right.value),
+            origin: self.index,
+            size: 1,
+            symbol: None,
         });
         Ok(OpCodeResult::Ok)
     }
     fn slt(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let left = stack.pop().ok_or(())?;
         let right = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: if left.value.signed_cmp(&right.value) == Ordering::Less {
+                Hex::new(1)
+            } else {
+                Hex::new(0)
+            },
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn sgt(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
         let left = stack.pop().ok_or(())?;
+        let right = stack.pop().ok_or(())?;
         stack.push(StackElement {
-            value: if left.value < right.value {
-                Hex(1)
+            value: if left.value.signed_cmp(&right.value) == Ordering::Greater {
+                Hex::new(1)
             } else {
-                Hex(0)
+                Hex::new(0)
             },
             origin: self.index,
             size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn signextend(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let byte = stack.pop().ok_or(())?;
+        let value = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: value.value.signextend(byte.value),
+            origin: self.index,
+            size: 1,
+            symbol: None,
         });
         Ok(OpCodeResult::Ok)
     }
@@ -64,6 +186,7 @@ impl Instruction {
             value: left.value * right.value,
             origin: self.index,
             size: 1,
+            symbol: None,
         });
         Ok(OpCodeResult::Ok)
     }
@@ -72,47 +195,118 @@ impl Instruction {
         let right = stack.pop().ok_or(())?;
         stack.push(StackElement {
             value: if left.value < right.value {
-                Hex(1)
+                Hex::new(1)
+            } else {
+                Hex::new(0)
+            },
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn gt(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let left = stack.pop().ok_or(())?;
+        let right = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: if left.value > right.value {
+                Hex::new(1)
             } else {
-                Hex(0)
+                Hex::new(0)
             },
             origin: self.index,
             size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn and(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let left = stack.pop().ok_or(())?;
+        let right = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: left.value & right.value,
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn or(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let left = stack.pop().ok_or(())?;
+        let right = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: left.value | right.value,
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn xor(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let left = stack.pop().ok_or(())?;
+        let right = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: left.value ^ right.value,
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn not(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let value = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: !value.value,
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn byte(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let index = stack.pop().ok_or(())?;
+        let value = stack.pop().ok_or(())?;
+        // Bytes are counted from the most significant end; out of range indices yield zero.
+        let byte = match usize::try_from(index.value) {
+            Ok(index) if index < 32 => Hex::from(value.value.to_be_bytes()[index]),
+            _ => Hex::new(0),
+        };
+        stack.push(StackElement {
+            value: byte,
+            origin: self.index,
+            size: 1,
+            symbol: None,
         });
         Ok(OpCodeResult::Ok)
     }
     fn swapx(&self, num_swap: u32, stack: &mut Stack) -> Result<OpCodeResult, ()> {
         let num_swap = num_swap as usize;
-        assert!(stack.len() >= num_swap);
+        if stack.len() <= num_swap {
+            return Err(());
+        }
         stack.swap(stack.len() - num_swap - 1, stack.len() - 1);
 
         Ok(OpCodeResult::Ok)
     }
     fn pushx(&self, num_push: usize, stack: &mut Stack, pc: &mut Hex) -> Result<OpCodeResult, ()> {
-        *pc += Hex(1) + Hex((num_push - 1) as u128);
+        *pc += Hex::from(num_push);
         assert!(self.args.len() == num_push);
-        let mut value = Hex(0);
-        for element in &self.args {
-            value = value << Hex(8);
-            value += *element;
-        }
         stack.push(StackElement {
-            value,
+            value: self.push_value(),
             origin: self.index,
             size: num_push,
+            symbol: None,
         });
         Ok(OpCodeResult::Ok)
     }
 
     fn dupx(&self, num_dup: usize, stack: &mut Stack) -> Result<OpCodeResult, ()> {
-        let dup = stack.get(stack.len() - num_dup);
-        assert!(dup.is_some());
-        if let Some(value) = dup {
-            stack.push(value.clone());
-            Ok(OpCodeResult::Ok)
-        } else {
-            Err(())
+        if stack.len() < num_dup {
+            return Err(());
         }
+        let value = stack.get(stack.len() - num_dup).ok_or(())?.clone();
+        stack.push(value);
+        Ok(OpCodeResult::Ok)
     }
     fn shr(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
         let shift = stack.pop().ok_or(())?;
@@ -122,14 +316,251 @@ impl Instruction {
             value: value.value >> shift.value,
             origin: self.index,
             size: 1,
+            symbol: None,
+        });
+
+        Ok(OpCodeResult::Ok)
+    }
+
+    fn shl(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let shift = stack.pop().ok_or(())?;
+        let value = stack.pop().ok_or(())?;
+
+        stack.push(StackElement {
+            value: value.value << shift.value,
+            origin: self.index,
+            size: 1,
+            symbol: None,
         });
 
         Ok(OpCodeResult::Ok)
     }
+    fn sar(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let shift = stack.pop().ok_or(())?;
+        let value = stack.pop().ok_or(())?;
 
-    fn sload(&self, stack: &mut Stack, memory: &Memory) -> Result<OpCodeResult, ()> {
+        stack.push(StackElement {
+            value: value.value.sar(shift.value),
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+
+        Ok(OpCodeResult::Ok)
+    }
+
+    fn sload(&self, stack: &mut Stack, storage: &Storage) -> Result<OpCodeResult, ()> {
+        let key = stack.pop().ok_or(())?;
+        let result = storage.sload(key.value);
+        stack.push(StackElement {
+            value: result.value,
+            origin: if let Some(origin) = result.origin {
+                origin
+            } else {
+                self.index
+            },
+            size: 32,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn sstore(&self, stack: &mut Stack, storage: &mut Storage) -> Result<OpCodeResult, ()> {
         let key = stack.pop().ok_or(())?;
-        stack.push(memory.sload(key.value));
+        let value = stack.pop().ok_or(())?;
+        storage.sstore(key.value, value);
+        Ok(OpCodeResult::Ok)
+    }
+    fn tload(&self, stack: &mut Stack, storage: &Storage) -> Result<OpCodeResult, ()> {
+        let key = stack.pop().ok_or(())?;
+        let result = storage.tload(key.value);
+        stack.push(StackElement {
+            value: result.value,
+            origin: result.origin.unwrap_or(self.index),
+            size: 32,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn tstore(&self, stack: &mut Stack, storage: &mut Storage) -> Result<OpCodeResult, ()> {
+        let key = stack.pop().ok_or(())?;
+        let value = stack.pop().ok_or(())?;
+        storage.tstore(key.value, value);
+        Ok(OpCodeResult::Ok)
+    }
+
+    // The value a PUSH pushes: its immediate as a big-endian number.
+    pub fn push_value(&self) -> Hex {
+        self.args
+            .iter()
+            .fold(Hex::ZERO, |value, arg| (value << Hex::new(8)) + *arg)
+    }
+    // The immediate bytes of the instruction.
+    fn immediate(&self) -> Vec<u8> {
+        self.args.iter().map(|arg| arg.as_usize() as u8).collect()
+    }
+    // Where RJUMP, RJUMPI or the cases of RJUMPV land, which is known without executing them.
+    pub fn relative_targets(&self) -> Vec<Hex> {
+        let next = self.index.as_usize() + 1 + self.args.len();
+        eof::relative_offsets(&self.opcode.code, &self.immediate())
+            .into_iter()
+            .filter_map(|offset| eof::relative_target(next, offset))
+            .map(Hex::from)
+            .collect()
+    }
+    // A relative jump by `offset` from the instruction after this one.
+    fn relative_jump(&self, offset: i16, jump_type: JumpType) -> Result<JumpInstruction, ()> {
+        let next = self.index.as_usize() + 1 + self.args.len();
+        let target = eof::relative_target(next, offset).ok_or(())?;
+        Ok(JumpInstruction {
+            instruction: self.clone(),
+            jump_type,
+            target: StackElement {
+                value: Hex::from(target),
+                origin: self.index,
+                size: 2,
+                symbol: None,
+            },
+            condition: None,
+            source: self.index,
+        })
+    }
+    fn rjump(&self, pc: &mut Hex) -> Result<OpCodeResult, ()> {
+        *pc += Hex::from(self.args.len());
+        let offsets = eof::relative_offsets(&self.opcode.code, &self.immediate());
+        let jump = self.relative_jump(*offsets.first().ok_or(())?, JumpType::Unconditional)?;
+        Ok(OpCodeResult::JumpInstruction(jump))
+    }
+    fn rjumpi(&self, stack: &mut Stack, pc: &mut Hex) -> Result<OpCodeResult, ()> {
+        let condition = stack.pop().ok_or(())?;
+        *pc += Hex::from(self.args.len());
+        let offsets = eof::relative_offsets(&self.opcode.code, &self.immediate());
+        let mut jump = self.relative_jump(*offsets.first().ok_or(())?, JumpType::Conditional)?;
+        jump.condition = Some(condition);
+        Ok(OpCodeResult::ConditionalJumpInstruction(jump))
+    }
+    // Jumps to the case selected by the index on the stack, or falls through when there is no
+    // case for it.
+    fn rjumpv(&self, stack: &mut Stack, pc: &mut Hex) -> Result<OpCodeResult, ()> {
+        let case = stack.pop().ok_or(())?;
+        *pc += Hex::from(self.args.len());
+        let offsets = eof::relative_offsets(&self.opcode.code, &self.immediate());
+        match usize::try_from(case.value)
+            .ok()
+            .and_then(|case| offsets.get(case))
+        {
+            Some(offset) => Ok(OpCodeResult::JumpInstruction(
+                self.relative_jump(*offset, JumpType::Unconditional)?,
+            )),
+            None => Ok(OpCodeResult::Ok),
+        }
+    }
+
+    // The offset and contents of the data section of the EOF container being executed.
+    fn data_section(container: Option<&Container>) -> Result<(usize, &[u8]), ()> {
+        let container = container.ok_or(())?;
+        Ok((container.data_offset, &container.data))
+    }
+    fn push_data_word(&self, stack: &mut Stack, data: &[u8], offset: Hex) {
+        let mut word = [0u8; 32];
+        if let Ok(offset) = usize::try_from(offset) {
+            for (i, byte) in word.iter_mut().enumerate() {
+                *byte = data.get(offset.saturating_add(i)).copied().unwrap_or(0);
+            }
+        }
+        stack.push(StackElement {
+            value: Hex::from_be_bytes(&word),
+            origin: self.index,
+            size: 32,
+            symbol: None,
+        });
+    }
+    fn dataload(
+        &self,
+        stack: &mut Stack,
+        container: Option<&Container>,
+    ) -> Result<OpCodeResult, ()> {
+        let offset = stack.pop().ok_or(())?;
+        let (_, data) = Self::data_section(container)?;
+        self.push_data_word(stack, data, offset.value);
+        Ok(OpCodeResult::Ok)
+    }
+    fn dataloadn(
+        &self,
+        stack: &mut Stack,
+        pc: &mut Hex,
+        container: Option<&Container>,
+    ) -> Result<OpCodeResult, ()> {
+        *pc += Hex::from(self.args.len());
+        let immediate = self.immediate();
+        let offset = u16::from_be_bytes([immediate[0], immediate[1]]);
+        let (_, data) = Self::data_section(container)?;
+        self.push_data_word(stack, data, Hex::from(offset as usize));
+        Ok(OpCodeResult::Ok)
+    }
+    fn datasize(
+        &self,
+        stack: &mut Stack,
+        container: Option<&Container>,
+    ) -> Result<OpCodeResult, ()> {
+        let (_, data) = Self::data_section(container)?;
+        stack.push(StackElement {
+            value: Hex::from(data.len()),
+            origin: self.index,
+            size: 2,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn datacopy(
+        &self,
+        stack: &mut Stack,
+        memory: &mut Memory,
+        container: Option<&Container>,
+    ) -> Result<OpCodeResult, ()> {
+        let dest = stack.pop().ok_or(())?;
+        let offset = stack.pop().ok_or(())?;
+        let size = stack.pop().ok_or(())?;
+        let (data_offset, data) = Self::data_section(container)?;
+        self.copy_to_memory(
+            memory,
+            dest.value,
+            offset.value,
+            size.value,
+            |position| {
+                usize::try_from(position)
+                    .ok()
+                    .and_then(|position| data.get(position).copied())
+                    .unwrap_or(0)
+            },
+            |position| ByteSource::Code(position + Hex::from(data_offset)),
+        )?;
+        Ok(OpCodeResult::Ok)
+    }
+
+    fn dupn(&self, stack: &mut Stack, pc: &mut Hex) -> Result<OpCodeResult, ()> {
+        *pc += Hex::from(self.args.len());
+        let depth = self.immediate()[0] as usize + 1;
+        self.dupx(depth, stack)
+    }
+    fn swapn(&self, stack: &mut Stack, pc: &mut Hex) -> Result<OpCodeResult, ()> {
+        *pc += Hex::from(self.args.len());
+        let depth = self.immediate()[0] as usize + 1;
+        if stack.len() <= depth {
+            return Err(());
+        }
+        stack.swap(stack.len() - depth - 1, stack.len() - 1);
+        Ok(OpCodeResult::Ok)
+    }
+    // Swaps the (n + 1)th and (n + m + 1)th items, counting from the top, where the immediate
+    // holds n - 1 and m - 1 in its high and low nibble.
+    fn exchange(&self, stack: &mut Stack, pc: &mut Hex) -> Result<OpCodeResult, ()> {
+        *pc += Hex::from(self.args.len());
+        let immediate = self.immediate()[0] as usize;
+        let (n, m) = ((immediate >> 4) + 1, (immediate & 0x0f) + 1);
+        if stack.len() <= n + m {
+            return Err(());
+        }
+        stack.swap(stack.len() - 1 - n, stack.len() - 1 - n - m);
         Ok(OpCodeResult::Ok)
     }
 
@@ -139,13 +570,13 @@ impl Instruction {
     fn jumpi(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
         let target = stack.pop().ok_or(())?;
         let condition = stack.pop().ok_or(())?;
-        println!("jumpi: target: {:?}, condition: {:?}", target, condition);
+        debug!("jumpi: target: {:?}, condition: {:?}", target, condition);
         let jump_instruction = JumpInstruction {
             instruction: self.clone(),
             jump_type: JumpType::Conditional,
             target,
             condition: Some(condition),
-            source: self.index.clone(),
+            source: self.index,
         };
 
         Ok(OpCodeResult::ConditionalJumpInstruction(jump_instruction))
@@ -156,7 +587,7 @@ impl Instruction {
             jump_type: JumpType::Unconditional,
             target: stack.pop().ok_or(())?,
             condition: None,
-            source: self.index.clone(),
+            source: self.index,
         };
 
         Ok(OpCodeResult::JumpInstruction(jump_instruction))
@@ -165,12 +596,142 @@ impl Instruction {
     fn mstore(&self, stack: &mut Stack, memory: &mut Memory) -> Result<OpCodeResult, ()> {
         let offset = stack.pop().ok_or(())?;
         let value = stack.pop().ok_or(())?;
-        memory.mstore(value, offset.value, self.index);
+        memory.mstore(value, offset.value, self.index)?;
+        Ok(OpCodeResult::Ok)
+    }
+    fn mstore8(&self, stack: &mut Stack, memory: &mut Memory) -> Result<OpCodeResult, ()> {
+        let offset = stack.pop().ok_or(())?;
+        let value = stack.pop().ok_or(())?;
+        memory.mstore8(value, offset.value)?;
+        Ok(OpCodeResult::Ok)
+    }
+    fn msize(&self, stack: &mut Stack, memory: &Memory) -> Result<OpCodeResult, ()> {
+        stack.push(StackElement {
+            value: Hex::from(memory.size()),
+            origin: self.index,
+            size: 1,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    // Copies `size` bytes read by `read` at `offset` and onwards into memory at `dest`. Every
+    // copied byte records where it was read from.
+    fn copy_to_memory(
+        &self,
+        memory: &mut Memory,
+        dest: Hex,
+        offset: Hex,
+        size: Hex,
+        read: impl Fn(Hex) -> u8,
+        source: impl Fn(Hex) -> ByteSource,
+    ) -> Result<(), ()> {
+        let size = usize::try_from(size)?;
+        if size > MAX_MEMORY_SIZE {
+            return Err(());
+        }
+        let bytes = (0..size)
+            .map(|i| {
+                let (position, overflow) = offset.overflowing_add(Hex::from(i));
+                MemoryElement {
+                    value: if overflow {
+                        Hex::new(0)
+                    } else {
+                        Hex::from(read(position))
+                    },
+                    origin: Some(self.index),
+                    symbol: None,
+                    source: Some(source(position)),
+                }
+            })
+            .collect();
+        memory.write_bytes(dest, bytes)
+    }
+    fn calldatacopy(
+        &self,
+        stack: &mut Stack,
+        memory: &mut Memory,
+        context: &ExecutionContext,
+    ) -> Result<OpCodeResult, ()> {
+        let dest = stack.pop().ok_or(())?;
+        let offset = stack.pop().ok_or(())?;
+        let size = stack.pop().ok_or(())?;
+        self.copy_to_memory(
+            memory,
+            dest.value,
+            offset.value,
+            size.value,
+            |position| context.calldata.byte(position),
+            ByteSource::CallData,
+        )?;
+        Ok(OpCodeResult::Ok)
+    }
+    fn codecopy(
+        &self,
+        stack: &mut Stack,
+        memory: &mut Memory,
+        code: &[u8],
+    ) -> Result<OpCodeResult, ()> {
+        let dest = stack.pop().ok_or(())?;
+        let offset = stack.pop().ok_or(())?;
+        let size = stack.pop().ok_or(())?;
+        self.copy_to_memory(
+            memory,
+            dest.value,
+            offset.value,
+            size.value,
+            |position| {
+                usize::try_from(position)
+                    .ok()
+                    .and_then(|position| code.get(position).copied())
+                    .unwrap_or(0)
+            },
+            ByteSource::Code,
+        )?;
+        Ok(OpCodeResult::Ok)
+    }
+    fn extcodecopy(&self, stack: &mut Stack, memory: &mut Memory) -> Result<OpCodeResult, ()> {
+        let address = stack.pop().ok_or(())?;
+        let dest = stack.pop().ok_or(())?;
+        let offset = stack.pop().ok_or(())?;
+        let size = stack.pop().ok_or(())?;
+        // The code of other accounts is not known, so it reads like an account without code.
+        self.copy_to_memory(
+            memory,
+            dest.value,
+            offset.value,
+            size.value,
+            |_| 0,
+            |offset| ByteSource::ExtCode {
+                address: address.value,
+                offset,
+            },
+        )?;
+        Ok(OpCodeResult::Ok)
+    }
+    fn returndatacopy(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
+        let _dest = stack.pop().ok_or(())?;
+        let offset = stack.pop().ok_or(())?;
+        let size = stack.pop().ok_or(())?;
+        // Calls are not executed, so there is never any return data. Unlike the other copy
+        // instructions, reading past the end of the return data halts execution.
+        if offset.value.is_zero() && size.value.is_zero() {
+            Ok(OpCodeResult::Ok)
+        } else {
+            Ok(OpCodeResult::End)
+        }
+    }
+    fn mcopy(&self, stack: &mut Stack, memory: &mut Memory) -> Result<OpCodeResult, ()> {
+        let dest = stack.pop().ok_or(())?;
+        let offset = stack.pop().ok_or(())?;
+        let size = stack.pop().ok_or(())?;
+        // The copied bytes keep their own origin, since they are moved rather than created.
+        let bytes = memory.read_bytes(offset.value, size.value)?;
+        memory.write_bytes(dest.value, bytes)?;
         Ok(OpCodeResult::Ok)
     }
     fn mload(&self, stack: &mut Stack, memory: &mut Memory) -> Result<OpCodeResult, ()> {
         let offset = stack.pop().ok_or(())?;
-        let result = memory.mload(offset.value);
+        let result = memory.mload(offset.value)?;
         stack.push(StackElement {
             value: result.value,
             origin: if let Some(origin) = result.origin {
@@ -179,52 +740,125 @@ impl Instruction {
                 self.index
             },
             size: 32,
+            symbol: result.symbol,
         });
         Ok(OpCodeResult::Ok)
     }
-
-    fn calldataload(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
-        let offset: usize = stack.pop().unwrap().value.0.try_into().unwrap();
-        if let Some(calldata) = unsafe { &CALLDATA } {
-            let value = calldata.get(offset).unwrap();
-            stack.push(value);
+    fn sha3(&self, stack: &mut Stack, memory: &mut Memory) -> Result<OpCodeResult, ()> {
+        let offset = stack.pop().ok_or(())?;
+        let size = stack.pop().ok_or(())?;
+        let elements = memory.read_bytes(offset.value, size.value)?;
+        let bytes: Vec<u8> = elements
+            .iter()
+            .map(|element| element.value.to_be_bytes()[31])
+            .collect();
+        // When part of the input is symbolic the hash is only a stand-in, so tag it with the
+        // memory range it was computed over.
+        let symbol = if elements.iter().any(|element| element.symbol.is_some()) {
+            Some(Symbol::MemoryHash {
+                start: offset.value,
+                end: offset.value + size.value,
+            })
         } else {
-            stack.push(StackElement {
-                origin: self.index,
-                value: Hex(0),
-                size: 1,
-            });
-        }
+            None
+        };
+        stack.push(StackElement {
+            value: Hex::from_be_bytes(&keccak256(&bytes)),
+            origin: self.index,
+            size: 32,
+            symbol,
+        });
         Ok(OpCodeResult::Ok)
     }
-    fn calldatasize(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
-        if let Some(calldata) = unsafe { &CALLDATA } {
-            let value = calldata.size();
-            stack.push(value);
-        } else {
-            stack.push(StackElement {
-                origin: self.index,
-                value: Hex(0),
-                size: 1,
-            });
-        }
+
+    fn calldataload(
+        &self,
+        stack: &mut Stack,
+        context: &ExecutionContext,
+    ) -> Result<OpCodeResult, ()> {
+        let offset = stack.pop().ok_or(())?;
+        stack.push(StackElement {
+            value: context.calldata.get(offset.value),
+            origin: self.index,
+            size: 32,
+            symbol: None,
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn calldatasize(
+        &self,
+        stack: &mut Stack,
+        context: &ExecutionContext,
+    ) -> Result<OpCodeResult, ()> {
+        stack.push(StackElement {
+            value: Hex::from(context.calldata.size()),
+            origin: self.index,
+            size: 32,
+            symbol: None,
+        });
         Ok(OpCodeResult::Ok)
     }
 
-    fn callvalue(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
-        if let Some(callvalue) = unsafe { &CALLVALUE } {
-            stack.push(StackElement {
+    // Pushes a value from the environment of the transaction, or a symbolic stand-in of zero
+    // when the value is not known.
+    fn environment(
+        &self,
+        stack: &mut Stack,
+        value: Option<Hex>,
+        name: &'static str,
+    ) -> Result<OpCodeResult, ()> {
+        stack.push(match value {
+            Some(value) => StackElement {
+                value,
                 origin: self.index,
-                value: callvalue.value,
-                size: callvalue.size,
-            });
-        } else {
-            stack.push(StackElement {
+                size: value.byte_len().max(1),
+                symbol: None,
+            },
+            None => StackElement {
+                value: Hex::new(0),
                 origin: self.index,
-                value: Hex(0),
-                size: 1,
-            });
-        }
+                size: 32,
+                symbol: Some(Symbol::UnknownEnvironment(name)),
+            },
+        });
+        Ok(OpCodeResult::Ok)
+    }
+    fn balance(&self, stack: &mut Stack, context: &ExecutionContext) -> Result<OpCodeResult, ()> {
+        let address = stack.pop().ok_or(())?;
+        let balance = context.balances.get(&address.value).copied();
+        self.environment(stack, balance, "BALANCE")
+    }
+    fn selfbalance(
+        &self,
+        stack: &mut Stack,
+        context: &ExecutionContext,
+    ) -> Result<OpCodeResult, ()> {
+        let balance = context
+            .address
+            .and_then(|address| context.balances.get(&address).copied());
+        self.environment(stack, balance, "SELFBALANCE")
+    }
+    // Indexes past the blobs of the transaction read as zero.
+    fn blobhash(&self, stack: &mut Stack, context: &ExecutionContext) -> Result<OpCodeResult, ()> {
+        let index = stack.pop().ok_or(())?;
+        let hash = context.blob_hashes.as_ref().map(|hashes| {
+            usize::try_from(index.value)
+                .ok()
+                .and_then(|index| hashes.get(index).copied())
+                .unwrap_or(Hex::new(0))
+        });
+        self.environment(stack, hash, "BLOBHASH")
+    }
+
+    // Transactions without a known call value are treated as sending no value.
+    fn callvalue(&self, stack: &mut Stack, context: &ExecutionContext) -> Result<OpCodeResult, ()> {
+        let value = context.callvalue.unwrap_or(Hex::new(0));
+        stack.push(StackElement {
+            origin: self.index,
+            value,
+            size: value.byte_len().max(1),
+            symbol: None,
+        });
         Ok(OpCodeResult::Ok)
     }
     fn eq(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
@@ -233,60 +867,72 @@ impl Instruction {
 
         stack.push(StackElement {
             value: if left.value == right.value {
-                Hex(1)
+                Hex::new(1)
             } else {
-                Hex(0)
+                Hex::new(0)
             },
             origin: self.index,
             size: 1,
+            symbol: None,
         });
         Ok(OpCodeResult::Ok)
     }
     fn is_zero(&self, stack: &mut Stack) -> Result<OpCodeResult, ()> {
         let value = stack.pop().ok_or(())?;
         stack.push(StackElement {
-            value: if value.value == Hex(0) {
-                Hex(1)
+            value: if value.value == Hex::new(0) {
+                Hex::new(1)
             } else {
-                Hex(0)
+                Hex::new(0)
             },
             origin: self.index,
             size: 1,
+            symbol: None,
         });
         Ok(OpCodeResult::Ok)
     }
-    // Parses the opcode and returns the stack
+    // Parses the opcode and returns the stack. `container` is the EOF container `code` was
+    // parsed into, which the data section instructions read from.
+    #[allow(clippy::too_many_arguments)]
     pub fn parse(
         &self,
         stack: &mut Stack,
         pc: &mut Hex,
         memory: &mut Memory,
+        storage: &mut Storage,
+        code: &[u8],
+        container: Option<&Container>,
+        context: &ExecutionContext,
     ) -> Result<OpCodeResult, ()> {
         match self.opcode.code {
             OpCodes::ADD => self.add(stack),
-            OpCodes::ADDMOD => todo!(),
-            OpCodes::ADDRESS => todo!(),
-            OpCodes::AND => todo!(),
-            OpCodes::BALANCE => todo!(),
-            OpCodes::BASEFEE => todo!(),
+            OpCodes::ADDMOD => self.addmod(stack),
+            OpCodes::ADDRESS => self.environment(stack, context.address, "ADDRESS"),
+            OpCodes::AND => self.and(stack),
+            OpCodes::BALANCE => self.balance(stack, context),
+            OpCodes::BASEFEE => self.environment(stack, context.block.base_fee, "BASEFEE"),
+            OpCodes::BLOBBASEFEE => {
+                self.environment(stack, context.block.blob_base_fee, "BLOBBASEFEE")
+            }
+            OpCodes::BLOBHASH => self.blobhash(stack, context),
             OpCodes::BLOCKHASH => todo!(),
-            OpCodes::BYTE => todo!(),
+            OpCodes::BYTE => self.byte(stack),
             OpCodes::CALL => todo!(),
             OpCodes::CALLCODE => todo!(),
-            OpCodes::CALLDATACOPY => todo!(),
-            OpCodes::CALLDATALOAD => self.calldataload(stack),
-            OpCodes::CALLDATASIZE => self.calldatasize(stack),
-            OpCodes::CALLER => todo!(),
-            OpCodes::CALLVALUE => self.callvalue(stack),
-            OpCodes::CHAINID => todo!(),
-            OpCodes::CODECOPY => todo!(),
-            OpCodes::CODESIZE => todo!(),
-            OpCodes::COINBASE => todo!(),
+            OpCodes::CALLDATACOPY => self.calldatacopy(stack, memory, context),
+            OpCodes::CALLDATALOAD => self.calldataload(stack, context),
+            OpCodes::CALLDATASIZE => self.calldatasize(stack, context),
+            OpCodes::CALLER => self.environment(stack, context.caller, "CALLER"),
+            OpCodes::CALLVALUE => self.callvalue(stack, context),
+            OpCodes::CHAINID => self.environment(stack, context.block.chain_id, "CHAINID"),
+            OpCodes::CODECOPY => self.codecopy(stack, memory, code),
+            OpCodes::CODESIZE => self.environment(stack, Some(Hex::from(code.len())), "CODESIZE"),
+            OpCodes::COINBASE => self.environment(stack, context.block.coinbase, "COINBASE"),
             OpCodes::CREATE => todo!(),
             OpCodes::CREATE2 => todo!(),
             OpCodes::DELEGATECALL => todo!(),
-            OpCodes::DIFFICULTY => todo!(),
-            OpCodes::DIV => todo!(),
+            OpCodes::DIFFICULTY => self.environment(stack, context.block.prevrandao, "PREVRANDAO"),
+            OpCodes::DIV => self.div(stack),
             OpCodes::DUP1 => self.dupx(1, stack),
             OpCodes::DUP2 => self.dupx(2, stack),
             OpCodes::DUP3 => self.dupx(3, stack),
@@ -303,17 +949,30 @@ impl Instruction {
             OpCodes::DUP14 => self.dupx(14, stack),
             OpCodes::DUP15 => self.dupx(15, stack),
             OpCodes::DUP16 => self.dupx(16, stack),
-            OpCodes::EOFMAGIC => todo!(),
+            // 0xef is not an instruction, so executing it fails like INVALID does.
+            OpCodes::EOFMAGIC => self.stop(stack),
+            OpCodes::DATALOAD => self.dataload(stack, container),
+            OpCodes::DATALOADN => self.dataloadn(stack, pc, container),
+            OpCodes::DATASIZE => self.datasize(stack, container),
+            OpCodes::DATACOPY => self.datacopy(stack, memory, container),
+            OpCodes::RJUMP => self.rjump(pc),
+            OpCodes::RJUMPI => self.rjumpi(stack, pc),
+            OpCodes::RJUMPV => self.rjumpv(stack, pc),
+            // Calls between code sections are not followed, so flows end at them.
+            OpCodes::CALLF | OpCodes::RETF | OpCodes::JUMPF => self.stop(stack),
+            OpCodes::DUPN => self.dupn(stack, pc),
+            OpCodes::SWAPN => self.swapn(stack, pc),
+            OpCodes::EXCHANGE => self.exchange(stack, pc),
             OpCodes::EQ => self.eq(stack),
-            OpCodes::EXP => todo!(),
-            OpCodes::EXTCODECOPY => todo!(),
+            OpCodes::EXP => self.exp(stack),
+            OpCodes::EXTCODECOPY => self.extcodecopy(stack, memory),
             OpCodes::EXTCODEHASH => todo!(),
             OpCodes::EXTCODESIZE => todo!(),
             OpCodes::GAS => todo!(),
-            OpCodes::GASLIMIT => todo!(),
-            OpCodes::GASPRICE => todo!(),
-            OpCodes::GT => todo!(),
-            OpCodes::INVALID => todo!(),
+            OpCodes::GASLIMIT => self.environment(stack, context.block.gas_limit, "GASLIMIT"),
+            OpCodes::GASPRICE => self.environment(stack, context.gas_price, "GASPRICE"),
+            OpCodes::GT => self.gt(stack),
+            OpCodes::INVALID => self.stop(stack),
             OpCodes::ISZERO => self.is_zero(stack),
             OpCodes::JUMP => self.jump(stack),
             OpCodes::JUMPDEST => self.jumpdest(),
@@ -324,20 +983,21 @@ impl Instruction {
             OpCodes::LOG3 => todo!(),
             OpCodes::LOG4 => todo!(),
             OpCodes::LT => self.lt(stack),
+            OpCodes::MCOPY => self.mcopy(stack, memory),
             OpCodes::MLOAD => self.mload(stack, memory),
-            OpCodes::MOD => todo!(),
-            OpCodes::MSIZE => todo!(),
+            OpCodes::MOD => self.modulo(stack),
+            OpCodes::MSIZE => self.msize(stack, memory),
             OpCodes::MSTORE => self.mstore(stack, memory),
-            OpCodes::MSTORE8 => todo!(),
+            OpCodes::MSTORE8 => self.mstore8(stack, memory),
             OpCodes::MUL => self.mul(stack),
-            OpCodes::MULMOD => todo!(),
-            OpCodes::NOT => todo!(),
-            OpCodes::NUMBER => todo!(),
-            OpCodes::OR => todo!(),
-            OpCodes::ORIGIN => todo!(),
+            OpCodes::MULMOD => self.mulmod(stack),
+            OpCodes::NOT => self.not(stack),
+            OpCodes::NUMBER => self.environment(stack, context.block.number, "NUMBER"),
+            OpCodes::OR => self.or(stack),
+            OpCodes::ORIGIN => self.environment(stack, context.origin, "ORIGIN"),
             OpCodes::PC => todo!(),
             OpCodes::POP => self.pop(stack),
-            OpCodes::PUSH0 => todo!(),
+            OpCodes::PUSH0 => self.pushx(0, stack, pc),
             OpCodes::PUSH1 => self.pushx(1, stack, pc),
             OpCodes::PUSH2 => self.pushx(2, stack, pc),
             OpCodes::PUSH3 => self.pushx(3, stack, pc),
@@ -371,22 +1031,22 @@ impl Instruction {
             OpCodes::PUSH31 => self.pushx(31, stack, pc),
             OpCodes::PUSH32 => self.pushx(32, stack, pc),
             OpCodes::RETURN => self.stop(stack),
-            OpCodes::RETURNDATACOPY => todo!(),
+            OpCodes::RETURNDATACOPY => self.returndatacopy(stack),
             OpCodes::RETURNDATASIZE => todo!(),
             OpCodes::REVERT => self.stop(stack),
-            OpCodes::SAR => todo!(),
-            OpCodes::SDIV => todo!(),
-            OpCodes::SELFBALANCE => todo!(),
+            OpCodes::SAR => self.sar(stack),
+            OpCodes::SDIV => self.sdiv(stack),
+            OpCodes::SELFBALANCE => self.selfbalance(stack, context),
             OpCodes::SELFDESTRUCT => todo!(),
-            OpCodes::SGT => todo!(),
-            OpCodes::SHA3 => todo!(),
-            OpCodes::SHL => todo!(),
+            OpCodes::SGT => self.sgt(stack),
+            OpCodes::SHA3 => self.sha3(stack, memory),
+            OpCodes::SHL => self.shl(stack),
             OpCodes::SHR => self.shr(stack),
-            OpCodes::SIGNEXTEND => todo!(),
-            OpCodes::SLOAD => self.sload(stack, memory),
+            OpCodes::SIGNEXTEND => self.signextend(stack),
+            OpCodes::SLOAD => self.sload(stack, storage),
             OpCodes::SLT => self.slt(stack),
-            OpCodes::SMOD => todo!(),
-            OpCodes::SSTORE => todo!(),
+            OpCodes::SMOD => self.smod(stack),
+            OpCodes::SSTORE => self.sstore(stack, storage),
             OpCodes::STATICCALL => todo!(),
             OpCodes::STOP => self.stop(stack),
             OpCodes::SUB => self.sub(stack),
@@ -406,20 +1066,17 @@ impl Instruction {
             OpCodes::SWAP14 => self.swapx(14, stack),
             OpCodes::SWAP15 => self.swapx(15, stack),
             OpCodes::SWAP16 => self.swapx(16, stack),
-            OpCodes::TIMESTAMP => todo!(),
-            OpCodes::XOR => todo!(),
+            OpCodes::TLOAD => self.tload(stack, storage),
+            OpCodes::TSTORE => self.tstore(stack, storage),
+            OpCodes::TIMESTAMP => self.environment(stack, context.block.timestamp, "TIMESTAMP"),
+            OpCodes::XOR => self.xor(stack),
         }
     }
 }
-#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
-pub struct ParsedInstruction {
-    pub instruction: Instruction,
-    pub used_arg: Option<Hex>,
-}
-
 impl std::fmt::Debug for Instruction {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{:<10?}\t args: {:?}", self.opcode, self.args)
+        let opcode = format!("{}(0x{:02x})", self.opcode.short_name, self.opcode_byte());
+        write!(f, "{:<10}\t args: {:?}", opcode, self.args)
     }
 }
 
@@ -437,6 +1094,22 @@ pub struct JumpInstruction {
     pub source: Hex,
     pub condition: Option<StackElement>,
 }
+impl JumpInstruction {
+    // The instruction the jump lands on, or None when the target is symbolic or not a JUMPDEST,
+    // in which case the jump halts exceptionally. Relative jumps in EOF code are checked when
+    // the container is validated and need no JUMPDEST.
+    pub fn destination<'a>(
+        &self,
+        instructions: &'a HashMap<Hex, Instruction>,
+    ) -> Option<&'a Instruction> {
+        if self.target.symbol.is_some() {
+            return None;
+        }
+        instructions.get(&self.target.value).filter(|destination| {
+            self.instruction.opcode.eof_only || destination.opcode.code == OpCodes::JUMPDEST
+        })
+    }
+}
 impl std::fmt::Debug for JumpInstruction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -472,82 +1145,574 @@ impl std::fmt::Debug for InstructionSet {
 #[cfg(test)]
 mod tests {
     use crate::{
+        calldata::CallData,
+        context::ExecutionContext,
+        eof::Container,
         hex::Hex,
-        memory::{Memory, MemoryElement},
+        memory::{ByteSource, Memory, MemoryElement},
         opcode::{
             opcodes, OpCodeResult,
             OpCodes::{self},
         },
-        stack::{Stack, StackElement},
+        stack::{Stack, StackElement, Symbol},
+        storage::Storage,
     };
 
     use super::Instruction;
     use test_log::test;
 
+    // Executes an instruction with an immediate at offset 0x10, returning the stack, the
+    // result and how far the program counter moved.
+    fn execute_immediate(
+        opcode: OpCodes,
+        immediate: &[u8],
+        inputs: &[u128],
+        code: &[u8],
+    ) -> (Stack, OpCodeResult, Hex) {
+        let mut stack = Stack::new();
+        for value in inputs {
+            stack.push(StackElement {
+                value: Hex::new(*value),
+                origin: Hex::new(0),
+                size: 1,
+                symbol: None,
+            });
+        }
+        let input = Instruction {
+            args: immediate.iter().map(|byte| Hex::from(*byte)).collect(),
+            opcode: opcodes().get(&opcode).unwrap().clone(),
+            index: Hex::new(0x10),
+            raw_byte: None,
+        };
+        let mut pc = Hex::new(0);
+        let result = input
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut Memory::new(),
+                &mut Storage::new(),
+                code,
+                Container::parse(code).ok().as_ref(),
+                &ExecutionContext::new(),
+            )
+            .unwrap();
+        (stack, result, pc)
+    }
+
+    fn values(stack: &Stack) -> Vec<Hex> {
+        (0..stack.len())
+            .map(|i| stack.get(i).unwrap().value)
+            .collect()
+    }
+
+    #[test]
+    fn dupn_swapn_exchange() {
+        let hex = |values: &[u128]| values.iter().map(|v| Hex::new(*v)).collect::<Vec<_>>();
+        let (stack, _, pc) = execute_immediate(OpCodes::DUPN, &[2], &[1, 2, 3, 4], &[]);
+        assert_eq!(values(&stack), hex(&[1, 2, 3, 4, 2]));
+        assert_eq!(pc, Hex::new(1));
+        let (stack, _, _) = execute_immediate(OpCodes::SWAPN, &[1], &[1, 2, 3, 4], &[]);
+        assert_eq!(values(&stack), hex(&[1, 4, 3, 2]));
+        // Swaps the second and the fourth item from the top.
+        let (stack, _, _) = execute_immediate(OpCodes::EXCHANGE, &[0x01], &[1, 2, 3, 4], &[]);
+        assert_eq!(values(&stack), hex(&[3, 2, 1, 4]));
+    }
+
+    #[test]
+    fn relative_jumps() {
+        let (_, result, pc) = execute_immediate(OpCodes::RJUMP, &[0xff, 0xfd], &[], &[]);
+        assert_eq!(pc, Hex::new(2));
+        match result {
+            OpCodeResult::JumpInstruction(jump) => assert_eq!(jump.target.value, Hex::new(0x10)),
+            _ => panic!("RJUMP should jump"),
+        }
+
+        // Two cases, jumping 5 bytes forward and 5 bytes back from 0x16.
+        let immediate = [0x01, 0x00, 0x05, 0xff, 0xfb];
+        let target = |case| match execute_immediate(OpCodes::RJUMPV, &immediate, &[case], &[]).1 {
+            OpCodeResult::JumpInstruction(jump) => Some(jump.target.value),
+            _ => None,
+        };
+        assert_eq!(target(0), Some(Hex::new(0x1b)));
+        assert_eq!(target(1), Some(Hex::new(0x11)));
+        assert_eq!(target(2), None);
+
+        let (stack, result, _) = execute_immediate(OpCodes::RJUMPI, &[0x00, 0x01], &[7], &[]);
+        assert_eq!(stack.len(), 0);
+        match result {
+            OpCodeResult::ConditionalJumpInstruction(jump) => {
+                assert_eq!(jump.target.value, Hex::new(0x14));
+                assert_eq!(jump.condition.unwrap().value, Hex::new(7));
+            }
+            _ => panic!("RJUMPI should jump conditionally"),
+        }
+    }
+
+    #[test]
+    fn data_section() {
+        // A container with a STOP and 3 bytes of data.
+        let code = [
+            0xef, 0x00, 0x01, 0x01, 0x00, 0x04, 0x02, 0x00, 0x01, 0x00, 0x01, 0x04, 0x00, 0x03,
+            0x00, 0x00, 0x80, 0x00, 0x00, 0x00, 0xaa, 0xbb, 0xcc,
+        ];
+        let (stack, _, pc) = execute_immediate(OpCodes::DATALOADN, &[0x00, 0x01], &[], &code);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0xbbcc) << 240);
+        assert_eq!(pc, Hex::new(2));
+        let (stack, _, _) = execute_immediate(OpCodes::DATASIZE, &[], &[], &code);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(3));
+        let (stack, _, _) = execute_immediate(OpCodes::DATALOAD, &[], &[0], &code);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0xaabbcc) << 232);
+    }
+
+    // Runs a single opcode on a stack built from `inputs`, where the last input ends up on top
+    // of the stack.
+    fn execute(opcode: OpCodes, inputs: &[Hex]) -> Stack {
+        execute_with_memory(
+            opcode,
+            inputs,
+            &mut Memory::new(),
+            &[],
+            &ExecutionContext::new(),
+        )
+        .0
+    }
+
+    fn execute_with_memory(
+        opcode: OpCodes,
+        inputs: &[Hex],
+        memory: &mut Memory,
+        code: &[u8],
+        context: &ExecutionContext,
+    ) -> (Stack, OpCodeResult) {
+        let mut stack = Stack::new();
+        for (origin, value) in inputs.iter().enumerate() {
+            stack.push(StackElement {
+                value: *value,
+                origin: Hex::from(origin),
+                size: 1,
+                symbol: None,
+            });
+        }
+        let input = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&opcode).unwrap().clone(),
+            index: Hex::from(inputs.len()),
+            raw_byte: None,
+        };
+        let mut pc = Hex::new(0);
+        let mut storage = Storage::new();
+        let result = input
+            .parse(
+                &mut stack,
+                &mut pc,
+                memory,
+                &mut storage,
+                code,
+                None,
+                context,
+            )
+            .unwrap();
+        (stack, result)
+    }
+
     #[test]
     fn pushx_1() {
         let input = Instruction {
-            args: Vec::from([Hex(0xff)]),
+            args: Vec::from([Hex::new(0xff)]),
             opcode: opcodes().get(&OpCodes::PUSH1).unwrap().clone(),
-            index: Hex(0),
+            index: Hex::new(0),
+            raw_byte: None,
         };
         let mut stack = Stack::new();
         let mut memory = Memory::new();
-        let mut pc = Hex(0);
-        match input.parse(&mut stack, &mut pc, &mut memory) {
+        let mut storage = Storage::new();
+        let mut pc = Hex::new(0);
+        match input.parse(
+            &mut stack,
+            &mut pc,
+            &mut memory,
+            &mut storage,
+            &[],
+            None,
+            &ExecutionContext::new(),
+        ) {
             Ok(v) => assert!(v == OpCodeResult::Ok),
             Err(_) => panic!("parse returned error where none was expected"),
         }
         assert!(stack.len() == 1);
         let elem = stack.pop().unwrap();
-        assert_eq!(elem.value, Hex(0xff));
+        assert_eq!(elem.value, Hex::new(0xff));
         assert_eq!(elem.size, 1);
-        assert_eq!(elem.origin, Hex(0));
-        assert_eq!(pc, Hex(1));
+        assert_eq!(elem.origin, Hex::new(0));
+        assert_eq!(pc, Hex::new(1));
     }
 
     #[test]
     fn pushx_5() {
         let input = Instruction {
-            args: Vec::from([Hex(0xff), Hex(0xee), Hex(0xdd), Hex(0xcc), Hex(0xbb)]),
+            args: Vec::from([
+                Hex::new(0xff),
+                Hex::new(0xee),
+                Hex::new(0xdd),
+                Hex::new(0xcc),
+                Hex::new(0xbb),
+            ]),
             opcode: opcodes().get(&OpCodes::PUSH5).unwrap().clone(),
-            index: Hex(0),
+            index: Hex::new(0),
+            raw_byte: None,
         };
         let mut stack = Stack::new();
         let mut memory = Memory::new();
-        let mut pc = Hex(0);
-        match input.parse(&mut stack, &mut pc, &mut memory) {
+        let mut storage = Storage::new();
+        let mut pc = Hex::new(0);
+        match input.parse(
+            &mut stack,
+            &mut pc,
+            &mut memory,
+            &mut storage,
+            &[],
+            None,
+            &ExecutionContext::new(),
+        ) {
             Ok(v) => assert!(v == OpCodeResult::Ok),
             Err(_) => panic!("parse returned error where none was expected"),
         }
         assert!(stack.len() == 1);
         let elem = stack.pop().unwrap();
-        assert_eq!(elem.value, Hex(0xffeeddccbb));
+        assert_eq!(elem.value, Hex::new(0xffeeddccbb));
         assert_eq!(elem.size, 5);
-        assert_eq!(elem.origin, Hex(0));
-        assert_eq!(pc, Hex(5));
+        assert_eq!(elem.origin, Hex::new(0));
+        assert_eq!(pc, Hex::new(5));
+    }
+    #[test]
+    fn pushx_32() {
+        let input = Instruction {
+            args: (0..32).map(|_| Hex::new(0xff)).collect(),
+            opcode: opcodes().get(&OpCodes::PUSH32).unwrap().clone(),
+            index: Hex::new(0),
+            raw_byte: None,
+        };
+        let mut stack = Stack::new();
+        let mut memory = Memory::new();
+        let mut storage = Storage::new();
+        let mut pc = Hex::new(0);
+        match input.parse(
+            &mut stack,
+            &mut pc,
+            &mut memory,
+            &mut storage,
+            &[],
+            None,
+            &ExecutionContext::new(),
+        ) {
+            Ok(v) => assert!(v == OpCodeResult::Ok),
+            Err(_) => panic!("parse returned error where none was expected"),
+        }
+        let elem = stack.pop().unwrap();
+        assert_eq!(elem.value, Hex::MAX);
+        assert_eq!(elem.size, 32);
+        assert_eq!(pc, Hex::new(32));
+    }
+    #[test]
+    fn calldataload_reads_from_context() {
+        let context = ExecutionContext {
+            calldata: CallData::from_hex("a9059cbb").unwrap(),
+            callvalue: Some(Hex::new(0x100)),
+            ..Default::default()
+        };
+        let mut memory = Memory::new();
+        let (stack, _) = execute_with_memory(
+            OpCodes::CALLDATALOAD,
+            &[Hex::new(1)],
+            &mut memory,
+            &[],
+            &context,
+        );
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0x059cbb) << 232);
+        let (stack, _) =
+            execute_with_memory(OpCodes::CALLDATASIZE, &[], &mut memory, &[], &context);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(4));
+        let (stack, _) = execute_with_memory(OpCodes::CALLVALUE, &[], &mut memory, &[], &context);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0x100));
+        assert_eq!(stack.get(0).unwrap().size, 2);
+    }
+
+    #[test]
+    fn environment_values() {
+        let mut context = ExecutionContext {
+            caller: Some(Hex::new(0xca11e7)),
+            address: Some(Hex::new(0xc0de)),
+            ..Default::default()
+        };
+        context.balances.insert(Hex::new(0xc0de), Hex::new(1000));
+        let mut memory = Memory::new();
+        let (stack, _) = execute_with_memory(OpCodes::CALLER, &[], &mut memory, &[], &context);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0xca11e7));
+        assert_eq!(stack.get(0).unwrap().symbol, None);
+        let (stack, _) = execute_with_memory(OpCodes::SELFBALANCE, &[], &mut memory, &[], &context);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(1000));
+        let (stack, _) =
+            execute_with_memory(OpCodes::BALANCE, &[Hex::new(1)], &mut memory, &[], &context);
+        assert_eq!(
+            stack.get(0).unwrap().symbol,
+            Some(Symbol::UnknownEnvironment("BALANCE"))
+        );
+        let (stack, _) = execute_with_memory(OpCodes::ORIGIN, &[], &mut memory, &[], &context);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+        assert_eq!(
+            format!("{:?}", stack.get(0).unwrap()),
+            "unknown environment value ORIGIN"
+        );
+    }
+
+    #[test]
+    fn push0() {
+        let stack = execute(OpCodes::PUSH0, &[]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+    }
+
+    #[test]
+    fn blobhash() {
+        let context = ExecutionContext {
+            blob_hashes: Some(vec![Hex::new(0x01aa)]),
+            ..Default::default()
+        };
+        let mut memory = Memory::new();
+        let (stack, _) = execute_with_memory(
+            OpCodes::BLOBHASH,
+            &[Hex::new(0)],
+            &mut memory,
+            &[],
+            &context,
+        );
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0x01aa));
+        let (stack, _) = execute_with_memory(
+            OpCodes::BLOBHASH,
+            &[Hex::new(1)],
+            &mut memory,
+            &[],
+            &context,
+        );
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+        assert_eq!(stack.get(0).unwrap().symbol, None);
+        let stack = execute(OpCodes::BLOBHASH, &[Hex::new(0)]);
+        assert_eq!(
+            stack.get(0).unwrap().symbol,
+            Some(Symbol::UnknownEnvironment("BLOBHASH"))
+        );
+    }
+
+    #[test]
+    fn codecopy_pads_past_end_of_code() {
+        let mut memory = Memory::new();
+        let code = [0x60, 0xab, 0x00];
+        execute_with_memory(
+            OpCodes::CODECOPY,
+            &[Hex::new(4), Hex::new(1), Hex::new(0x10)],
+            &mut memory,
+            &code,
+            &ExecutionContext::new(),
+        );
+        assert_eq!(memory.size(), 0x20);
+        let copied = &memory.get_contents()[0x10..0x14];
+        let values: Vec<Hex> = copied.iter().map(|byte| byte.value).collect();
+        assert_eq!(
+            values,
+            [Hex::new(0xab), Hex::new(0), Hex::new(0), Hex::new(0)]
+        );
+        assert_eq!(copied[0].origin, Some(Hex::new(3)));
+        assert_eq!(copied[0].source, Some(ByteSource::Code(Hex::new(1))));
+        assert_eq!(copied[3].source.as_ref().unwrap().to_string(), "code[0x4]");
+        assert_eq!(memory.get_contents()[0x14].source, None);
+
+        let (stack, _) = execute_with_memory(
+            OpCodes::CODESIZE,
+            &[],
+            &mut memory,
+            &code,
+            &ExecutionContext::new(),
+        );
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(3));
+    }
+
+    #[test]
+    fn zero_sized_copy_does_not_expand_memory() {
+        let mut memory = Memory::new();
+        execute_with_memory(
+            OpCodes::CODECOPY,
+            &[Hex::new(0), Hex::MAX, Hex::MAX],
+            &mut memory,
+            &[],
+            &ExecutionContext::new(),
+        );
+        assert_eq!(memory.size(), 0);
+    }
+
+    #[test]
+    fn extcodecopy_records_address() {
+        let mut memory = Memory::new();
+        execute_with_memory(
+            OpCodes::EXTCODECOPY,
+            &[Hex::new(1), Hex::new(0x20), Hex::new(0), Hex::new(0xbeef)],
+            &mut memory,
+            &[],
+            &ExecutionContext::new(),
+        );
+        assert_eq!(
+            memory.get_contents()[0]
+                .source
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "extcode(0xbeef)[0x20]"
+        );
+    }
+
+    #[test]
+    fn mcopy_keeps_origin() {
+        let mut memory = Memory::new();
+        memory
+            .mstore8(
+                StackElement {
+                    value: Hex::new(0xff),
+                    origin: Hex::new(7),
+                    size: 1,
+                    symbol: None,
+                },
+                Hex::new(0),
+            )
+            .unwrap();
+        execute_with_memory(
+            OpCodes::MCOPY,
+            &[Hex::new(2), Hex::new(0), Hex::new(0x41)],
+            &mut memory,
+            &[],
+            &ExecutionContext::new(),
+        );
+        assert_eq!(memory.size(), 0x60);
+        assert_eq!(memory.get_contents()[0x41].value, Hex::new(0xff));
+        assert_eq!(memory.get_contents()[0x41].origin, Some(Hex::new(7)));
+        assert_eq!(memory.get_contents()[0x42].value, Hex::new(0));
+    }
+
+    #[test]
+    fn returndatacopy_without_return_data_halts() {
+        let mut memory = Memory::new();
+        let (_, result) = execute_with_memory(
+            OpCodes::RETURNDATACOPY,
+            &[Hex::new(0), Hex::new(0), Hex::new(0)],
+            &mut memory,
+            &[],
+            &ExecutionContext::new(),
+        );
+        assert!(matches!(result, OpCodeResult::Ok));
+        let (_, result) = execute_with_memory(
+            OpCodes::RETURNDATACOPY,
+            &[Hex::new(1), Hex::new(0), Hex::new(0)],
+            &mut memory,
+            &[],
+            &ExecutionContext::new(),
+        );
+        assert!(matches!(result, OpCodeResult::End));
+        assert_eq!(memory.size(), 0);
+    }
+
+    #[test]
+    fn mstore_mload_full_word() {
+        let mut stack = Stack::new();
+        stack.push(StackElement {
+            value: Hex::MAX - Hex::new(1),
+            origin: Hex::new(0),
+            size: 1,
+            symbol: None,
+        });
+        stack.push(StackElement {
+            value: Hex::new(0),
+            origin: Hex::new(1),
+            size: 1,
+            symbol: None,
+        });
+        let mstore = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&OpCodes::MSTORE).unwrap().clone(),
+            index: Hex::new(2),
+            raw_byte: None,
+        };
+        let mload = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&OpCodes::MLOAD).unwrap().clone(),
+            index: Hex::new(5),
+            raw_byte: None,
+        };
+        let mut memory = Memory::new();
+        let mut storage = Storage::new();
+        let mut pc = Hex::new(0);
+        mstore
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new(),
+            )
+            .unwrap();
+        stack.push(StackElement {
+            value: Hex::new(0),
+            origin: Hex::new(4),
+            size: 1,
+            symbol: None,
+        });
+        mload
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new(),
+            )
+            .unwrap();
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::MAX - Hex::new(1));
+        assert_eq!(stack.get(0).unwrap().origin, Hex::new(0));
     }
     #[test]
     fn mstore_basic() {
         let input = Instruction {
             args: Vec::new(),
             opcode: opcodes().get(&OpCodes::MSTORE).unwrap().clone(),
-            index: Hex(2),
+            index: Hex::new(2),
+            raw_byte: None,
         };
         let mut stack = Stack::new();
         stack.push(StackElement {
-            value: Hex(0xabcd),
-            origin: Hex(0),
+            value: Hex::new(0xabcd),
+            origin: Hex::new(0),
             size: 2,
+            symbol: None,
         });
         stack.push(StackElement {
-            value: Hex(0),
-            origin: Hex(1),
+            value: Hex::new(0),
+            origin: Hex::new(1),
             size: 1,
+            symbol: None,
         });
         let mut memory = Memory::new();
-        let mut pc = Hex(0);
-        match input.parse(&mut stack, &mut pc, &mut memory) {
+        let mut storage = Storage::new();
+        let mut pc = Hex::new(0);
+        match input.parse(
+            &mut stack,
+            &mut pc,
+            &mut memory,
+            &mut storage,
+            &[],
+            None,
+            &ExecutionContext::new(),
+        ) {
             Ok(v) => assert!(v == OpCodeResult::Ok),
             Err(_) => panic!("parse returned error where none was expected"),
         }
@@ -556,15 +1721,19 @@ mod tests {
         assert_eq!(
             memory.get_contents().get(30),
             Some(&MemoryElement {
-                value: Hex(0xab),
-                origin: Some(Hex(0))
+                value: Hex::new(0xab),
+                origin: Some(Hex::new(0)),
+                symbol: None,
+                source: None,
             })
         );
         assert_eq!(
             memory.get_contents().get(31),
             Some(&MemoryElement {
-                value: Hex(0xcd),
-                origin: Some(Hex(0))
+                value: Hex::new(0xcd),
+                origin: Some(Hex::new(0)),
+                symbol: None,
+                source: None,
             })
         );
     }
@@ -573,72 +1742,103 @@ mod tests {
         let first_input = Instruction {
             args: Vec::new(),
             opcode: opcodes().get(&OpCodes::MSTORE).unwrap().clone(),
-            index: Hex(2),
+            index: Hex::new(2),
+            raw_byte: None,
         };
         let second_input = Instruction {
             args: Vec::new(),
             opcode: opcodes().get(&OpCodes::MSTORE).unwrap().clone(),
-            index: Hex(5),
+            index: Hex::new(5),
+            raw_byte: None,
         };
         let mut stack = Stack::new();
         stack.push(StackElement {
-            value: Hex(0x1234),
-            origin: Hex(4),
+            value: Hex::new(0x1234),
+            origin: Hex::new(4),
             size: 2,
+            symbol: None,
         });
         stack.push(StackElement {
-            value: Hex(1),
-            origin: Hex(3),
+            value: Hex::new(1),
+            origin: Hex::new(3),
             size: 1,
+            symbol: None,
         });
         stack.push(StackElement {
-            value: Hex(0xabcd),
-            origin: Hex(0),
+            value: Hex::new(0xabcd),
+            origin: Hex::new(0),
             size: 2,
+            symbol: None,
         });
         stack.push(StackElement {
-            value: Hex(0),
-            origin: Hex(1),
+            value: Hex::new(0),
+            origin: Hex::new(1),
             size: 1,
+            symbol: None,
         });
         let mut memory = Memory::new();
-        let mut pc = Hex(0);
-        match first_input.parse(&mut stack, &mut pc, &mut memory) {
+        let mut storage = Storage::new();
+        let mut pc = Hex::new(0);
+        match first_input.parse(
+            &mut stack,
+            &mut pc,
+            &mut memory,
+            &mut storage,
+            &[],
+            None,
+            &ExecutionContext::new(),
+        ) {
             Ok(v) => assert!(v == OpCodeResult::Ok),
             Err(_) => panic!("parse returned error where none was expected"),
         }
         assert_eq!(
             memory.get_contents().get(31),
             Some(&MemoryElement {
-                value: Hex(0xcd),
-                origin: Some(Hex(0)),
+                value: Hex::new(0xcd),
+                origin: Some(Hex::new(0)),
+                symbol: None,
+                source: None,
             })
         );
-        match second_input.parse(&mut stack, &mut pc, &mut memory) {
+        match second_input.parse(
+            &mut stack,
+            &mut pc,
+            &mut memory,
+            &mut storage,
+            &[],
+            None,
+            &ExecutionContext::new(),
+        ) {
             Ok(v) => assert!(v == OpCodeResult::Ok),
             Err(_) => panic!("parse returned error where none was expected"),
         }
         assert!(stack.len() == 0);
         assert_eq!(memory.get_contents().len(), 64);
         assert_eq!(
-            memory.get_contents().get(0),
+            memory.get_contents().first(),
             Some(&MemoryElement {
-                value: Hex(0),
-                origin: Some(Hex(2)),
+                value: Hex::new(0),
+                origin: Some(Hex::new(2)),
+                symbol: None,
+                source: None,
             })
         );
         assert_eq!(
             memory.get_contents().get(31),
             Some(&MemoryElement {
-                value: Hex(0x12),
-                origin: Some(Hex(4)),
+                value: Hex::new(0x12),
+                origin: Some(Hex::new(4)),
+                symbol: None,
+                source: None,
             })
         );
         assert_eq!(
             memory.get_contents().get(32),
             Some(&MemoryElement {
-                value: Hex(0x34),
-                origin: Some(Hex(4))
+                value: Hex::new(0x34),
+                origin: Some(Hex::new(4)),
+                symbol: None,
+                source: None,
             })
         );
     }
@@ -647,56 +1847,123 @@ mod tests {
     fn dup1() {
         let mut stack = Stack::new();
         stack.push(StackElement {
-            value: Hex(1),
-            origin: Hex(0),
+            value: Hex::new(1),
+            origin: Hex::new(0),
             size: 1,
+            symbol: None,
         });
         let input = Instruction {
             args: Vec::new(),
             opcode: opcodes().get(&OpCodes::DUP1).unwrap().clone(),
-            index: Hex(2),
+            index: Hex::new(2),
+            raw_byte: None,
         };
-        let mut pc = Hex(0);
+        let mut pc = Hex::new(0);
         let mut memory = Memory::new();
-        input.parse(&mut stack, &mut pc, &mut memory).unwrap();
+        let mut storage = Storage::new();
+        input
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new(),
+            )
+            .unwrap();
         assert_eq!(stack.len(), 2);
         assert_eq!(stack.get(0), stack.get(1));
     }
 
     #[test]
-    #[should_panic]
     fn dup1_empty_stack() {
         let mut stack = Stack::new();
         let input = Instruction {
             args: Vec::new(),
             opcode: opcodes().get(&OpCodes::DUP1).unwrap().clone(),
-            index: Hex(2),
+            index: Hex::new(2),
+            raw_byte: None,
         };
-        let mut pc = Hex(0);
+        let mut pc = Hex::new(0);
         let mut memory = Memory::new();
-        assert!(input.parse(&mut stack, &mut pc, &mut memory).is_err());
+        let mut storage = Storage::new();
+        assert!(input
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new()
+            )
+            .is_err());
+    }
+    #[test]
+    fn swap1_one_item() {
+        let mut stack = Stack::new();
+        stack.push(StackElement {
+            value: Hex::new(1),
+            origin: Hex::new(0),
+            size: 1,
+            symbol: None,
+        });
+        let input = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&OpCodes::SWAP1).unwrap().clone(),
+            index: Hex::new(2),
+            raw_byte: None,
+        };
+        let mut pc = Hex::new(0);
+        assert!(input
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut Memory::new(),
+                &mut Storage::new(),
+                &[],
+                None,
+                &ExecutionContext::new()
+            )
+            .is_err());
+        assert_eq!(stack.len(), 1);
     }
     #[test]
     fn dup2() {
         let mut stack = Stack::new();
         stack.push(StackElement {
-            value: Hex(2),
-            origin: Hex(1),
+            value: Hex::new(2),
+            origin: Hex::new(1),
             size: 1,
+            symbol: None,
         });
         stack.push(StackElement {
-            value: Hex(1),
-            origin: Hex(0),
+            value: Hex::new(1),
+            origin: Hex::new(0),
             size: 1,
+            symbol: None,
         });
         let input = Instruction {
             args: Vec::new(),
             opcode: opcodes().get(&OpCodes::DUP2).unwrap().clone(),
-            index: Hex(2),
+            index: Hex::new(2),
+            raw_byte: None,
         };
-        let mut pc = Hex(0);
+        let mut pc = Hex::new(0);
         let mut memory = Memory::new();
-        input.parse(&mut stack, &mut pc, &mut memory).unwrap();
+        let mut storage = Storage::new();
+        input
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new(),
+            )
+            .unwrap();
         assert_eq!(stack.len(), 3);
         assert_eq!(stack.get(0), stack.get(2));
     }
@@ -704,48 +1971,518 @@ mod tests {
     fn shr() {
         let mut stack = Stack::new();
         stack.push(StackElement {
-            value: Hex(0xf),
-            origin: Hex(1),
+            value: Hex::new(0xf),
+            origin: Hex::new(1),
             size: 1,
+            symbol: None,
         });
         stack.push(StackElement {
-            value: Hex(1),
-            origin: Hex(0),
+            value: Hex::new(1),
+            origin: Hex::new(0),
             size: 1,
+            symbol: None,
         });
         let input = Instruction {
             args: Vec::new(),
             opcode: opcodes().get(&OpCodes::SHR).unwrap().clone(),
-            index: Hex(2),
+            index: Hex::new(2),
+            raw_byte: None,
         };
-        let mut pc = Hex(0);
+        let mut pc = Hex::new(0);
         let mut memory = Memory::new();
-        input.parse(&mut stack, &mut pc, &mut memory).unwrap();
+        let mut storage = Storage::new();
+        input
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new(),
+            )
+            .unwrap();
         assert_eq!(stack.len(), 1);
-        assert_eq!(stack.get(0).unwrap().value, Hex(0x7));
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0x7));
     }
     #[test]
     fn shr_overflow() {
         let mut stack = Stack::new();
         stack.push(StackElement {
-            value: Hex(0),
-            origin: Hex(0),
+            value: Hex::new(0),
+            origin: Hex::new(0),
             size: 1,
+            symbol: None,
         });
         stack.push(StackElement {
-            value: Hex(0xff),
-            origin: Hex(0x1),
+            value: Hex::new(0xff),
+            origin: Hex::new(0x1),
             size: 1,
+            symbol: None,
         });
         let input = Instruction {
             args: Vec::new(),
             opcode: opcodes().get(&OpCodes::SHR).unwrap().clone(),
-            index: Hex(2),
+            index: Hex::new(2),
+            raw_byte: None,
+        };
+        let mut pc = Hex::new(0);
+        let mut memory = Memory::new();
+        let mut storage = Storage::new();
+        input
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new(),
+            )
+            .unwrap();
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0x0));
+    }
+    #[test]
+    fn slt_negative() {
+        let stack = execute(OpCodes::SLT, &[Hex::new(1), Hex::from(-1)]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(1));
+        assert_eq!(stack.get(0).unwrap().origin, Hex::new(2));
+
+        let stack = execute(OpCodes::SLT, &[Hex::from(-1), Hex::new(1)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+    }
+    #[test]
+    fn sgt_negative() {
+        let stack = execute(OpCodes::SGT, &[Hex::from(-1), Hex::new(1)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(1));
+
+        let stack = execute(OpCodes::SGT, &[Hex::new(1), Hex::from(-1)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+    }
+    #[test]
+    fn sdiv() {
+        let stack = execute(OpCodes::SDIV, &[Hex::new(2), Hex::from(-10)]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::from(-5));
+
+        let stack = execute(OpCodes::SDIV, &[Hex::new(0), Hex::from(-10)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+    }
+    #[test]
+    fn smod() {
+        let stack = execute(OpCodes::SMOD, &[Hex::new(3), Hex::from(-8)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::from(-2));
+    }
+    #[test]
+    fn sar() {
+        let stack = execute(OpCodes::SAR, &[Hex::from(-16), Hex::new(4)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::from(-1));
+
+        let stack = execute(OpCodes::SAR, &[Hex::new(0x100), Hex::new(4)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0x10));
+    }
+    #[test]
+    fn signextend() {
+        let stack = execute(OpCodes::SIGNEXTEND, &[Hex::new(0xff), Hex::new(0)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::MAX);
+
+        let stack = execute(OpCodes::SIGNEXTEND, &[Hex::new(0x7fff), Hex::new(1)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0x7fff));
+    }
+    #[test]
+    fn div() {
+        let stack = execute(OpCodes::DIV, &[Hex::new(3), Hex::new(10)]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(3));
+        assert_eq!(stack.get(0).unwrap().origin, Hex::new(2));
+    }
+    #[test]
+    fn div_by_zero() {
+        let stack = execute(OpCodes::DIV, &[Hex::new(0), Hex::new(10)]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+    }
+    #[test]
+    fn modulo() {
+        let stack = execute(OpCodes::MOD, &[Hex::new(3), Hex::new(10)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(1));
+
+        let stack = execute(OpCodes::MOD, &[Hex::new(0), Hex::new(10)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+    }
+    #[test]
+    fn addmod() {
+        let stack = execute(OpCodes::ADDMOD, &[Hex::new(8), Hex::new(10), Hex::new(10)]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(4));
+
+        // The intermediate sum does not wrap at 2^256.
+        let stack = execute(OpCodes::ADDMOD, &[Hex::new(2), Hex::new(2), Hex::MAX]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(1));
+    }
+    #[test]
+    fn mulmod() {
+        let stack = execute(OpCodes::MULMOD, &[Hex::new(8), Hex::new(10), Hex::new(10)]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(4));
+
+        let stack = execute(OpCodes::MULMOD, &[Hex::new(12), Hex::MAX, Hex::MAX]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(9));
+
+        let stack = execute(OpCodes::MULMOD, &[Hex::new(0), Hex::MAX, Hex::MAX]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+    }
+    #[test]
+    fn exp() {
+        let stack = execute(OpCodes::EXP, &[Hex::new(8), Hex::new(2)]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0x100));
+
+        let stack = execute(OpCodes::EXP, &[Hex::new(256), Hex::new(2)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+    }
+    #[test]
+    fn gt() {
+        let stack = execute(OpCodes::GT, &[Hex::new(1), Hex::new(2)]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(1));
+        assert_eq!(stack.get(0).unwrap().origin, Hex::new(2));
+
+        let stack = execute(OpCodes::GT, &[Hex::MAX, Hex::new(2)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+    }
+    #[test]
+    fn and_address_mask() {
+        let mask = Hex::try_from(&"ff".repeat(20)).unwrap();
+        let value = Hex::MAX;
+        let stack = execute(OpCodes::AND, &[value, mask]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, mask);
+    }
+    #[test]
+    fn or_xor() {
+        let stack = execute(OpCodes::OR, &[Hex::new(0xf0), Hex::new(0x0f)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0xff));
+
+        let stack = execute(OpCodes::XOR, &[Hex::new(0xff), Hex::new(0x0f)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0xf0));
+    }
+    #[test]
+    fn not() {
+        let stack = execute(OpCodes::NOT, &[Hex::new(0)]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::MAX);
+    }
+    #[test]
+    fn byte() {
+        let value = Hex::new(0xab) << 248;
+        let stack = execute(OpCodes::BYTE, &[value, Hex::new(0)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0xab));
+
+        let stack = execute(OpCodes::BYTE, &[Hex::new(0xcd), Hex::new(31)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0xcd));
+
+        let stack = execute(OpCodes::BYTE, &[Hex::MAX, Hex::new(32)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+    }
+    #[test]
+    fn shl() {
+        let stack = execute(OpCodes::SHL, &[Hex::new(1), Hex::new(224)]);
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::ONE << 224);
+
+        let stack = execute(OpCodes::SHL, &[Hex::new(1), Hex::new(256)]);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0));
+    }
+    #[test]
+    fn sstore_sload() {
+        let mut stack = Stack::new();
+        stack.push(StackElement {
+            value: Hex::new(0xabcd),
+            origin: Hex::new(0),
+            size: 2,
+            symbol: None,
+        });
+        stack.push(StackElement {
+            value: Hex::MAX,
+            origin: Hex::new(3),
+            size: 32,
+            symbol: None,
+        });
+        let sstore = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&OpCodes::SSTORE).unwrap().clone(),
+            index: Hex::new(0x24),
+            raw_byte: None,
+        };
+        let sload = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&OpCodes::SLOAD).unwrap().clone(),
+            index: Hex::new(0x47),
+            raw_byte: None,
+        };
+        let mut pc = Hex::new(0);
+        let mut memory = Memory::new();
+        let mut storage = Storage::new();
+        sstore
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new(),
+            )
+            .unwrap();
+        assert_eq!(stack.len(), 0);
+        // Storing does not touch memory.
+        assert_eq!(memory.get_contents().len(), 0);
+
+        stack.push(StackElement {
+            value: Hex::MAX,
+            origin: Hex::new(0x45),
+            size: 32,
+            symbol: None,
+        });
+        sload
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new(),
+            )
+            .unwrap();
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0xabcd));
+        assert_eq!(stack.get(0).unwrap().origin, Hex::new(0));
+    }
+    #[test]
+    fn tstore_tload() {
+        let mut stack = Stack::new();
+        stack.push(StackElement {
+            value: Hex::new(0xabcd),
+            origin: Hex::new(0),
+            size: 2,
+            symbol: None,
+        });
+        stack.push(StackElement {
+            value: Hex::new(1),
+            origin: Hex::new(1),
+            size: 1,
+            symbol: None,
+        });
+        let tstore = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&OpCodes::TSTORE).unwrap().clone(),
+            index: Hex::new(2),
+            raw_byte: None,
+        };
+        let tload = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&OpCodes::TLOAD).unwrap().clone(),
+            index: Hex::new(4),
+            raw_byte: None,
+        };
+        let mut pc = Hex::new(0);
+        let mut memory = Memory::new();
+        let mut storage = Storage::new();
+        let context = ExecutionContext::new();
+        tstore
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &context,
+            )
+            .unwrap();
+        stack.push(StackElement {
+            value: Hex::new(1),
+            origin: Hex::new(3),
+            size: 1,
+            symbol: None,
+        });
+        tload
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &context,
+            )
+            .unwrap();
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0xabcd));
+        assert_eq!(stack.get(0).unwrap().origin, Hex::new(0));
+        assert_eq!(storage.sload(Hex::new(1)).value, Hex::new(0));
+    }
+
+    #[test]
+    fn sload_pre_state() {
+        let mut stack = Stack::new();
+        stack.push(StackElement {
+            value: Hex::new(1),
+            origin: Hex::new(0),
+            size: 1,
+            symbol: None,
+        });
+        let sload = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&OpCodes::SLOAD).unwrap().clone(),
+            index: Hex::new(2),
+            raw_byte: None,
+        };
+        let mut pc = Hex::new(0);
+        let mut memory = Memory::new();
+        let mut storage = Storage::from_slots([(Hex::new(1), Hex::new(0x42))]);
+        sload
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new(),
+            )
+            .unwrap();
+        assert_eq!(stack.get(0).unwrap().value, Hex::new(0x42));
+        assert_eq!(stack.get(0).unwrap().origin, Hex::new(2));
+    }
+    #[test]
+    fn sha3_concrete_memory() {
+        let mut stack = Stack::new();
+        stack.push(StackElement {
+            value: Hex::new(0x20),
+            origin: Hex::new(0),
+            size: 1,
+            symbol: None,
+        });
+        stack.push(StackElement {
+            value: Hex::new(0),
+            origin: Hex::new(2),
+            size: 1,
+            symbol: None,
+        });
+        let input = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&OpCodes::SHA3).unwrap().clone(),
+            index: Hex::new(4),
+            raw_byte: None,
+        };
+        let mut pc = Hex::new(0);
+        let mut memory = Memory::new();
+        let mut storage = Storage::new();
+        input
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new(),
+            )
+            .unwrap();
+        assert_eq!(stack.len(), 1);
+        // keccak256 of a zeroed word.
+        assert_eq!(
+            stack.get(0).unwrap().value,
+            Hex::try_from(
+                &"290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563".to_string()
+            )
+            .unwrap()
+        );
+        assert_eq!(stack.get(0).unwrap().origin, Hex::new(4));
+        assert_eq!(stack.get(0).unwrap().symbol, None);
+        assert_eq!(memory.get_contents().len(), 32);
+    }
+    #[test]
+    fn sha3_symbolic_memory() {
+        let mut stack = Stack::new();
+        stack.push(StackElement {
+            value: Hex::new(0x40),
+            origin: Hex::new(0),
+            size: 1,
+            symbol: None,
+        });
+        stack.push(StackElement {
+            value: Hex::new(0),
+            origin: Hex::new(1),
+            size: 1,
+            symbol: None,
+        });
+        // A symbolic value stored in the second word of the hashed range.
+        stack.push(StackElement {
+            value: Hex::new(0x1234),
+            origin: Hex::new(2),
+            size: 32,
+            symbol: Some(Symbol::MemoryHash {
+                start: Hex::new(0x80),
+                end: Hex::new(0xa0),
+            }),
+        });
+        stack.push(StackElement {
+            value: Hex::new(0x20),
+            origin: Hex::new(3),
+            size: 1,
+            symbol: None,
+        });
+        let mstore = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&OpCodes::MSTORE).unwrap().clone(),
+            index: Hex::new(4),
+            raw_byte: None,
+        };
+        let sha3 = Instruction {
+            args: Vec::new(),
+            opcode: opcodes().get(&OpCodes::SHA3).unwrap().clone(),
+            index: Hex::new(5),
+            raw_byte: None,
         };
-        let mut pc = Hex(0);
+        let mut pc = Hex::new(0);
         let mut memory = Memory::new();
-        input.parse(&mut stack, &mut pc, &mut memory).unwrap();
+        let mut storage = Storage::new();
+        mstore
+            .parse(
+                &mut stack,
+                &mut pc,
+                &mut memory,
+                &mut storage,
+                &[],
+                None,
+                &ExecutionContext::new(),
+            )
+            .unwrap();
+        sha3.parse(
+            &mut stack,
+            &mut pc,
+            &mut memory,
+            &mut storage,
+            &[],
+            None,
+            &ExecutionContext::new(),
+        )
+        .unwrap();
         assert_eq!(stack.len(), 1);
-        assert_eq!(stack.get(0).unwrap().value, Hex(0x0));
+        let hash = stack.get(0).unwrap();
+        assert_eq!(
+            hash.symbol,
+            Some(Symbol::MemoryHash {
+                start: Hex::new(0),
+                end: Hex::new(0x40),
+            })
+        );
+        assert_eq!(format!("{:?}", hash), "keccak256(memory[0x0..0x40])");
     }
 }
@@ -0,0 +1,387 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, Write},
+};
+
+use serde::Serialize;
+
+use crate::{
+    flow::{Flow, ParsedInstructionSet},
+    hex::Hex,
+    instruction::{Instruction, JumpInstruction, JumpType},
+    parser::Parser,
+    stack::StackElement,
+};
+
+// Bumped whenever a field is removed or changes meaning; new fields may be added without it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+// A program to report, named like the clusters of the DOT output.
+pub struct Program<'a> {
+    pub name: &'a str,
+    pub parser: &'a Parser,
+    pub flows: &'a [Flow],
+}
+
+// The JSON output, with every word written as a hexadecimal string since most do not fit in a
+// JSON number:
+//
+//     {"version": 1, "programs": [{"name": "code", "instructions": [...], ...}]}
+#[derive(Serialize)]
+struct Report {
+    version: u32,
+    programs: Vec<ProgramReport>,
+}
+
+#[derive(Serialize)]
+struct ProgramReport {
+    name: String,
+    instructions: Vec<InstructionReport>,
+    instruction_sets: Vec<InstructionSetReport>,
+    flows: Vec<FlowReport>,
+}
+
+#[derive(Serialize)]
+struct InstructionReport {
+    offset: String,
+    opcode: String,
+    byte: String,
+    immediate: Option<String>,
+}
+
+#[derive(Serialize)]
+struct InstructionSetReport {
+    start: String,
+    end: String,
+}
+
+#[derive(Serialize)]
+struct FlowReport {
+    steps: Vec<StepReport>,
+}
+
+// A step of a flow, with the stack from bottom to top and the memory as they are when the
+// step is left.
+#[derive(Serialize)]
+struct StepReport {
+    start: String,
+    end: String,
+    jump: Option<JumpReport>,
+    stack: Vec<ValueReport>,
+    memory: String,
+}
+
+#[derive(Serialize)]
+struct JumpReport {
+    // The offset of the jump instruction itself.
+    offset: String,
+    opcode: String,
+    #[serde(rename = "type")]
+    jump_type: &'static str,
+    target: ValueReport,
+    condition: Option<ValueReport>,
+    // Whether the target is a concrete offset the jump can land on, rather than a symbolic value
+    // or an offset that makes the jump halt exceptionally.
+    resolved: bool,
+}
+
+#[derive(Serialize)]
+struct ValueReport {
+    value: String,
+    // The offset of the instruction that pushed the value.
+    origin: String,
+    symbol: Option<String>,
+}
+
+pub fn write_json(out: &mut impl Write, programs: &[Program]) -> io::Result<()> {
+    let report = Report {
+        version: SCHEMA_VERSION,
+        programs: programs.iter().map(program_report).collect(),
+    };
+    serde_json::to_writer_pretty(&mut *out, &report)?;
+    writeln!(out)
+}
+
+fn program_report(program: &Program) -> ProgramReport {
+    let instructions: BTreeMap<&Hex, &Instruction> =
+        program.parser.get_instructions().iter().collect();
+    let instruction_sets: BTreeMap<Hex, Hex> = program
+        .parser
+        .get_instruction_sets()
+        .into_values()
+        .map(|set| (set.start, set.end))
+        .collect();
+    ProgramReport {
+        name: program.name.to_string(),
+        instructions: instructions
+            .values()
+            .map(|instruction| instruction_report(instruction))
+            .collect(),
+        instruction_sets: instruction_sets
+            .iter()
+            .map(|(start, end)| InstructionSetReport {
+                start: word(start),
+                end: word(end),
+            })
+            .collect(),
+        flows: program
+            .flows
+            .iter()
+            .map(|flow| FlowReport {
+                steps: flow
+                    .steps()
+                    .into_iter()
+                    .map(|step| step_report(step, program.parser.get_instructions()))
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+fn instruction_report(instruction: &Instruction) -> InstructionReport {
+    let immediate: String = instruction
+        .args
+        .iter()
+        .map(|arg| format!("{:02x}", arg))
+        .collect();
+    InstructionReport {
+        offset: word(&instruction.index),
+        opcode: instruction.opcode.short_name.clone(),
+        byte: format!("{:#04x}", instruction.opcode_byte()),
+        immediate: (!immediate.is_empty()).then(|| format!("0x{}", immediate)),
+    }
+}
+
+fn step_report(
+    step: &ParsedInstructionSet,
+    instructions: &HashMap<Hex, Instruction>,
+) -> StepReport {
+    let stack = (0..step.stack.len())
+        .filter_map(|index| step.stack.get(index))
+        .map(value_report)
+        .collect();
+    let memory: String = step
+        .memory
+        .get_contents()
+        .iter()
+        .map(|byte| format!("{:02x}", byte.value))
+        .collect();
+    StepReport {
+        start: word(&step.start),
+        end: word(&step.end),
+        jump: step
+            .jump
+            .as_ref()
+            .map(|jump| jump_report(jump, instructions)),
+        stack,
+        memory: format!("0x{}", memory),
+    }
+}
+
+fn jump_report(jump: &JumpInstruction, instructions: &HashMap<Hex, Instruction>) -> JumpReport {
+    JumpReport {
+        offset: word(&jump.instruction.index),
+        opcode: jump.instruction.opcode.short_name.clone(),
+        jump_type: match jump.jump_type {
+            JumpType::Conditional => "conditional",
+            JumpType::Unconditional => "unconditional",
+        },
+        target: value_report(&jump.target),
+        condition: jump.condition.as_ref().map(value_report),
+        resolved: jump.destination(instructions).is_some(),
+    }
+}
+
+fn value_report(element: &StackElement) -> ValueReport {
+    ValueReport {
+        value: word(&element.value),
+        origin: word(&element.origin),
+        symbol: element.symbol.as_ref().map(|symbol| symbol.to_string()),
+    }
+}
+
+fn word(value: &Hex) -> String {
+    format!("{:#x}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::{flow::Flow, flow_parser::FlowParser, parser::Parser, testing::parse};
+
+    use super::{write_json, Program, SCHEMA_VERSION};
+
+    fn json(parser: &Parser, flows: &[Flow]) -> Value {
+        let mut out = Vec::new();
+        write_json(
+            &mut out,
+            &[Program {
+                name: "code",
+                parser,
+                flows,
+            }],
+        )
+        .unwrap();
+        serde_json::from_slice(&out).unwrap()
+    }
+
+    fn flows(parser: &Parser) -> Vec<Flow> {
+        let mut flow_parser = FlowParser::new(parser.get_instructions(), parser.get_bytecode());
+        flow_parser.parse_flows();
+        flow_parser.flows().clone()
+    }
+
+    fn keys(value: &Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn report() {
+        let parser = parse(
+            "
+            PUSH1 0x2a
+            PUSH1 0x00
+            MSTORE
+            PUSH1 0x01
+            PUSH1 taken
+            JUMPI
+            STOP
+            taken:
+            JUMPDEST
+            STOP
+            ",
+        );
+        let report = json(&parser, &flows(&parser));
+        assert_eq!(report["version"], SCHEMA_VERSION);
+        let program = &report["programs"][0];
+        assert_eq!(program["name"], "code");
+        assert_eq!(program["instructions"][0]["offset"], "0x0");
+        assert_eq!(program["instructions"][0]["opcode"], "PUSH1");
+        assert_eq!(program["instructions"][0]["byte"], "0x60");
+        assert_eq!(program["instructions"][0]["immediate"], "0x2a");
+        assert_eq!(program["instructions"][2]["immediate"], Value::Null);
+        assert_eq!(program["instruction_sets"][0]["start"], "0x0");
+
+        // The JUMPI is followed both ways, and the flow that jumps has two steps.
+        let flows = program["flows"].as_array().unwrap();
+        assert_eq!(flows.len(), 2);
+        let flow = flows
+            .iter()
+            .find(|flow| flow["steps"].as_array().unwrap().len() == 2)
+            .unwrap();
+        let step = &flow["steps"][0];
+        let jump = &step["jump"];
+        assert_eq!(jump["offset"], "0x9");
+        assert_eq!(jump["opcode"], "JUMPI");
+        assert_eq!(jump["type"], "conditional");
+        assert_eq!(jump["target"]["value"], "0xb");
+        assert_eq!(jump["target"]["origin"], "0x7");
+        assert_eq!(jump["condition"]["value"], "0x1");
+        assert_eq!(jump["resolved"], true);
+        assert_eq!(step["memory"], format!("0x{}2a", "00".repeat(31)).as_str());
+        assert_eq!(flow["steps"][1]["start"], "0xb");
+    }
+
+    #[test]
+    fn schema() {
+        // Removing or renaming any of these fields needs a new SCHEMA_VERSION.
+        assert_eq!(SCHEMA_VERSION, 1);
+        let parser = parse("PUSH1 0x01\nPUSH1 taken\nJUMPI\nSTOP\ntaken:\nJUMPDEST\nSTOP");
+        let report = json(&parser, &flows(&parser));
+        assert_eq!(keys(&report), ["programs", "version"]);
+        assert_eq!(report["version"], 1);
+        let program = &report["programs"][0];
+        assert_eq!(
+            keys(program),
+            ["flows", "instruction_sets", "instructions", "name"]
+        );
+        assert_eq!(
+            keys(&program["instructions"][0]),
+            ["byte", "immediate", "offset", "opcode"]
+        );
+        assert_eq!(keys(&program["instruction_sets"][0]), ["end", "start"]);
+        assert_eq!(keys(&program["flows"][0]), ["steps"]);
+        let step = &program["flows"][0]["steps"][0];
+        assert_eq!(keys(step), ["end", "jump", "memory", "stack", "start"]);
+        assert_eq!(
+            keys(&step["jump"]),
+            [
+                "condition",
+                "offset",
+                "opcode",
+                "resolved",
+                "target",
+                "type"
+            ]
+        );
+        assert_eq!(keys(&step["jump"]["target"]), ["origin", "symbol", "value"]);
+    }
+
+    #[test]
+    fn unresolved_jumps() {
+        // The only step of the only flow of `source`, which ends at a jump that halts.
+        let jump = |source: &str| {
+            let parser = parse(source);
+            let report = json(&parser, &flows(&parser));
+            let flows = report["programs"][0]["flows"].as_array().unwrap().clone();
+            assert_eq!(flows.len(), 1);
+            assert_eq!(flows[0]["steps"].as_array().unwrap().len(), 1);
+            flows[0]["steps"][0]["jump"].clone()
+        };
+
+        // The caller is not part of the default context, so a target derived from it is
+        // symbolic.
+        let caller = jump("CALLER\nJUMP");
+        assert_eq!(caller["type"], "unconditional");
+        assert_eq!(caller["target"]["origin"], "0x0");
+        assert_eq!(
+            caller["target"]["symbol"],
+            "unknown environment value CALLER"
+        );
+        assert_eq!(caller["resolved"], false);
+
+        // A concrete target beyond the code is not an instruction.
+        let beyond = jump("PUSH1 0x40\nJUMP");
+        assert_eq!(beyond["target"]["value"], "0x40");
+        assert_eq!(beyond["target"]["symbol"], Value::Null);
+        assert_eq!(beyond["resolved"], false);
+
+        // A concrete target that is an instruction, but not a JUMPDEST.
+        let push = jump("PUSH1 0x00\nJUMP");
+        assert_eq!(push["target"]["value"], "0x0");
+        assert_eq!(push["resolved"], false);
+    }
+
+    #[test]
+    fn looping_flow() {
+        let parser = parse(
+            "
+            PUSH1 loop
+            JUMP
+            loop:
+            JUMPDEST
+            PUSH1 loop
+            JUMP
+            ",
+        );
+        let report = json(&parser, &flows(&parser));
+        let flows = report["programs"][0]["flows"].as_array().unwrap();
+        assert_eq!(flows.len(), 1);
+        // The steps end where the loop jumps back to a step that is already in the flow,
+        // instead of repeating it.
+        let steps = flows[0]["steps"].as_array().unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0]["start"], "0x0");
+        assert_eq!(steps[1]["start"], "0x3");
+        assert_eq!(steps[1]["jump"]["target"]["value"], "0x3");
+        assert_eq!(steps[1]["jump"]["resolved"], true);
+    }
+}
@@ -0,0 +1,155 @@
+// Keccak-256 as used by the EVM. This is the original Keccak submission, which differs from the
+// standardised SHA3-256 only in the padding delimiter.
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+// Rotation offsets and lane order for the combined rho and pi steps.
+const ROTATIONS: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+const PI_LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+// 1600 - 2 * 256 bits of capacity, in bytes.
+const RATE: usize = 136;
+
+fn keccak_f(state: &mut [u64; 25]) {
+    for round_constant in ROUND_CONSTANTS {
+        // theta
+        let mut columns = [0u64; 5];
+        for (x, column) in columns.iter_mut().enumerate() {
+            *column = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = columns[(x + 4) % 5] ^ columns[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[x + 5 * y] ^= d;
+            }
+        }
+
+        // rho and pi
+        let mut last = state[1];
+        for (rotation, lane) in ROTATIONS.iter().zip(PI_LANES) {
+            let current = state[lane];
+            state[lane] = last.rotate_left(*rotation);
+            last = current;
+        }
+
+        // chi
+        for y in 0..5 {
+            let row: [u64; 5] = std::array::from_fn(|x| state[x + 5 * y]);
+            for x in 0..5 {
+                state[x + 5 * y] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        // iota
+        state[0] ^= round_constant;
+    }
+}
+
+fn sponge(input: &[u8], delimiter: u8) -> [u8; 32] {
+    let mut state = [0u64; 25];
+    let mut padded = input.to_vec();
+    padded.push(delimiter);
+    while !padded.len().is_multiple_of(RATE) {
+        padded.push(0);
+    }
+    *padded.last_mut().unwrap() |= 0x80;
+
+    for block in padded.chunks(RATE) {
+        for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+            *lane ^= u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        keccak_f(&mut state);
+    }
+
+    let mut result = [0u8; 32];
+    for (bytes, lane) in result.chunks_mut(8).zip(state) {
+        bytes.copy_from_slice(&lane.to_le_bytes());
+    }
+    result
+}
+
+pub fn keccak256(input: &[u8]) -> [u8; 32] {
+    sponge(input, 0x01)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hex::Hex;
+
+    use super::{keccak256, sponge};
+
+    fn hash(input: &[u8]) -> Hex {
+        Hex::from_be_bytes(&keccak256(input))
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(
+            hash(b""),
+            Hex::try_from(
+                &"c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470".to_string()
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn short_input() {
+        assert_eq!(
+            hash(b"abc"),
+            Hex::try_from(
+                &"4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45".to_string()
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn function_selector() {
+        let selector = hash(b"transfer(address,uint256)") >> 224;
+        assert_eq!(selector, Hex::new(0xa9059cbb));
+    }
+
+    #[test]
+    fn multiple_blocks() {
+        // The SHA3-256 test vector for 200 bytes of 0xa3 exercises the permutation across
+        // more than one block.
+        let result = Hex::from_be_bytes(&sponge(&[0xa3; 200], 0x06));
+        assert_eq!(
+            result,
+            Hex::try_from(
+                &"79f38adec5c20307a98ef76e8324afbfd46cfd81b22e3973c65fa1bd9de31787".to_string()
+            )
+            .unwrap()
+        );
+    }
+}
@@ -1,42 +1,111 @@
+mod assembler;
 mod calldata;
+mod cbor;
+mod cfg;
+mod compiler;
+mod context;
+mod deploy;
+mod disasm;
+mod dot;
+mod eof;
 mod flow;
 mod flow_parser;
 mod hex;
 mod instruction;
+mod json;
+mod keccak;
 mod memory;
 mod opcode;
 mod parser;
+mod patch;
 mod stack;
-mod utils;
+mod storage;
+#[cfg(test)]
+mod testing;
 
 use calldata::CallData;
-use clap::Parser;
+use cfg::Cfg;
+use clap::{Parser, Subcommand};
+use context::ExecutionContext;
+use deploy::Deployment;
+use flow::Flow;
 use flow_parser::FlowParser;
 use hex::Hex;
-use log::{error, info, warn};
+use log::{debug, error, warn};
+use opcode::Hardfork;
 use parser::Parser as BytecodeParser;
-use stack::StackElement;
+use patch::Patch;
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{self, BufRead, BufReader, Error, ErrorKind},
     path::Path,
 };
-
-pub static mut CALLVALUE: Option<StackElement> = None;
-pub static mut CALLDATA: Option<CallData> = None;
+use storage::Storage;
 
 #[derive(Parser, Debug)]
 struct Args {
-    #[arg(long)]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(long, global = true)]
     input: Option<String>,
 
-    #[arg(long)]
+    #[arg(long, global = true)]
     filename: Option<String>,
 
     #[arg(long)]
     callvalue: Option<String>,
     #[arg(long)]
     calldata: Option<String>,
+    // The EVM version the bytecode was compiled for, which decides the available opcodes.
+    #[arg(long, value_enum, default_value_t, global = true)]
+    evm_version: Hardfork,
+    // JSON or TOML file describing the transaction and block, such as the caller.
+    #[arg(long)]
+    env: Option<String>,
+
+    // Storage pre-state as hexadecimal `slot=value` pairs, for example `--storage 0=ff`.
+    #[arg(long)]
+    storage: Vec<String>,
+
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+}
+
+// How the analysis is written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+enum Format {
+    // A summary of the code and its flows.
+    #[default]
+    Text,
+    // The control-flow graph as a Graphviz digraph.
+    Dot,
+    // The instructions, instruction sets and flows, in the versioned schema of the json module.
+    Json,
+}
+
+// Without a subcommand, the flows through the bytecode are analysed.
+#[derive(Subcommand, Debug)]
+enum Command {
+    // Writes a linear disassembly listing to stdout.
+    Disasm,
+    // Assembles the mnemonics in the given file and writes the bytecode to stdout as hex.
+    Asm {
+        source: String,
+    },
+    // Patches the bytecode and writes it to stdout as hex, keeping its jumps intact. Code is
+    // given as `offset=file` pairs, with the offset in hexadecimal and the file holding assembly.
+    Patch {
+        // Inserts code in front of the instruction at the offset.
+        #[arg(long)]
+        insert: Vec<String>,
+        // Replaces the instruction at the offset with code.
+        #[arg(long)]
+        replace: Vec<String>,
+        // Removes the instruction at the hexadecimal offset.
+        #[arg(long)]
+        remove: Vec<String>,
+    },
 }
 
 fn read_bytecode(input: String) -> Option<Vec<u32>> {
@@ -49,6 +118,10 @@ fn read_bytecode(input: String) -> Option<Vec<u32>> {
             return None;
         }
     }
+    if !input.len().is_multiple_of(2) {
+        warn!("Illegal input, contains an odd number of digits: {}", input);
+        return None;
+    }
 
     let radix: u32 = 16;
     let bytecode: Vec<char> = input.chars().collect();
@@ -59,37 +132,223 @@ fn read_bytecode(input: String) -> Option<Vec<u32>> {
         })
         .collect();
 
-    if bytecode.len() == 0 {
+    if bytecode.is_empty() {
         None
     } else {
         Some(bytecode)
     }
 }
 
-fn parse_args(args: &Args) -> Result<(), std::io::Error> {
+fn print_summary(parser: &BytecodeParser, flows: &[Flow]) {
+    let compiler = compiler::fingerprint(parser);
+    println!("compiler: {}", compiler);
+    if let Some(container) = parser.get_container() {
+        println!(
+            "eof: version {}, {} code sections, {} container sections, {} bytes of data",
+            container.version,
+            container.code_sections.len(),
+            container.container_sections.len(),
+            container.declared_data_size
+        );
+    }
+    if compiler.uses_push0 {
+        println!("evm version: shanghai or later, uses PUSH0");
+    }
+    println!("instructions: {}", parser.get_instructions().len());
+    println!("instruction sets: {}", parser.get_instruction_sets().len());
+    let cfg = build_cfg(parser, flows);
+    // Blocks without a known way in are only entered through unresolved jumps, if at all.
+    let orphans = cfg
+        .blocks()
+        .filter(|block| {
+            !cfg.entries().contains(&block.start) && cfg.predecessors(block.start).next().is_none()
+        })
+        .count();
+    println!(
+        "basic blocks: {} ({} reachable, {} without predecessors), edges: {}, unresolved jumps: {}",
+        cfg.blocks().count(),
+        cfg.reachable().len(),
+        orphans,
+        cfg.edges().count(),
+        cfg.unresolved().len()
+    );
+    println!("flows: {}", flows.len());
+}
+
+fn build_cfg(parser: &BytecodeParser, flows: &[Flow]) -> Cfg {
+    let mut cfg = Cfg::new(parser);
+    cfg.add_flows(parser, flows);
+    cfg
+}
+
+fn parse_flows<'a>(
+    parser: &'a BytecodeParser,
+    code: &'a [u8],
+    context: &ExecutionContext,
+    storage: &Storage,
+) -> FlowParser<'a> {
+    let mut flow_parser = FlowParser::new(parser.get_instructions(), code);
+    flow_parser.set_context(context.clone());
+    flow_parser.set_storage(storage.clone());
+    if let Some(container) = parser.get_container() {
+        flow_parser.set_container(container);
+    }
+    flow_parser.parse_flows();
+    warn!("{} flows found.", flow_parser.flows().len());
+    flow_parser
+}
+
+// Analyses the constructor and the runtime of creation code as separate programs. The
+// constructor runs with the complete creation code, which it copies the runtime and its
+// arguments from.
+fn print_deployment(
+    deployment: &Deployment,
+    bytecode: &[u8],
+    hardfork: Hardfork,
+    context: &ExecutionContext,
+    storage: &Storage,
+    format: Format,
+) -> Result<(), std::io::Error> {
+    let invalid = |e| Error::new(ErrorKind::InvalidData, e);
+    let constructor = deployment.constructor_parser(hardfork).map_err(invalid)?;
+    let runtime = deployment.runtime_parser(hardfork).map_err(invalid)?;
+    let constructor_flows = parse_flows(&constructor, bytecode, context, storage);
+    let runtime_flows = parse_flows(&runtime, &deployment.runtime, context, storage);
+    if format == Format::Json {
+        return json::write_json(
+            &mut io::stdout().lock(),
+            &[
+                json::Program {
+                    name: "constructor",
+                    parser: &constructor,
+                    flows: constructor_flows.flows(),
+                },
+                json::Program {
+                    name: "runtime",
+                    parser: &runtime,
+                    flows: runtime_flows.flows(),
+                },
+            ],
+        );
+    }
+    if format == Format::Dot {
+        let constructor_cfg = build_cfg(&constructor, constructor_flows.flows());
+        let runtime_cfg = build_cfg(&runtime, runtime_flows.flows());
+        return dot::write_dot(
+            &mut io::stdout().lock(),
+            &[
+                dot::Program {
+                    name: "constructor",
+                    cfg: &constructor_cfg,
+                    instructions: constructor.get_instructions(),
+                },
+                dot::Program {
+                    name: "runtime",
+                    cfg: &runtime_cfg,
+                    instructions: runtime.get_instructions(),
+                },
+            ],
+        );
+    }
+    println!(
+        "creation code: constructor 0x0000, runtime {:#06x} ({} bytes), copied at {:#06x}",
+        deployment.runtime_offset,
+        deployment.runtime.len(),
+        deployment.codecopy
+    );
+    match deployment.argument_words() {
+        Some(words) => {
+            println!("constructor arguments: {}", words.len());
+            for (index, word) in words.iter().enumerate() {
+                println!("  {:#06x}: {:#066x}", index * 32, word);
+            }
+        }
+        None => println!(
+            "constructor arguments: {} bytes, not ABI-encoded",
+            deployment.arguments.len()
+        ),
+    }
+    println!("\nconstructor");
+    print_summary(&constructor, constructor_flows.flows());
+    println!("\nruntime");
+    print_summary(&runtime, runtime_flows.flows());
+    for flow in constructor_flows
+        .flows()
+        .iter()
+        .chain(runtime_flows.flows())
+    {
+        warn!("flow");
+        flow.print();
+    }
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn parse_offset(offset: &str) -> Result<Hex, std::io::Error> {
+    Hex::from_str_radix(offset.trim_start_matches("0x"), 16)
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))
+}
+
+// Reads an `offset=file` pair and assembles the file.
+fn parse_patch_code(pair: &String) -> Result<(Hex, Vec<u8>), std::io::Error> {
+    let (offset, file) = pair.split_once('=').ok_or(Error::new(
+        ErrorKind::InvalidInput,
+        format!("Patch {} is not of the form offset=file", pair),
+    ))?;
+    let code = assembler::assemble(&fs::read_to_string(file)?)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    Ok((parse_offset(offset)?, code))
+}
+
+fn parse_args(args: &Args) -> Result<ExecutionContext, std::io::Error> {
+    let mut context = if let Some(env) = &args.env {
+        ExecutionContext::from_environment_file(Path::new(env))?
+    } else {
+        ExecutionContext::new()
+    };
     if let Some(callvalue) = &args.callvalue {
-        let value: Hex = match Hex::try_from(callvalue) {
+        let value: Hex = match Hex::from_str_radix(callvalue, 10) {
             Ok(v) => v,
             Err(e) => return Err(Error::new(ErrorKind::InvalidInput, e)),
         };
-        unsafe {
-            CALLVALUE = Some(StackElement {
-                value,
-                origin: Hex(0),
-                size: (format!("{:x}", value.0).len() + 1) / 2 as usize,
-            });
-        }
+        context.callvalue = Some(value);
     }
 
     if let Some(calldata) = &args.calldata {
-        unsafe { CALLDATA = Some(CallData::new(calldata.to_string())) }
+        context.calldata = CallData::from_hex(calldata)?;
     };
-    Ok(())
+    Ok(context)
+}
+
+fn parse_storage(args: &Args) -> Result<Storage, std::io::Error> {
+    let mut slots = Vec::new();
+    for slot in &args.storage {
+        let (key, value) = slot.split_once('=').ok_or(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Storage slot {} is not of the form slot=value", slot),
+        ))?;
+        let key = Hex::try_from(&key.trim_start_matches("0x").to_string())
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        let value = Hex::try_from(&value.trim_start_matches("0x").to_string())
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+        slots.push((key, value));
+    }
+    Ok(Storage::from_slots(slots))
 }
 
 fn main() -> Result<(), std::io::Error> {
     env_logger::init();
     let args = Args::parse();
+    if let Some(Command::Asm { source }) = &args.command {
+        let source = fs::read_to_string(source)?;
+        let bytecode =
+            assembler::assemble(&source).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        println!("{}", to_hex(&bytecode));
+        return Ok(());
+    }
     let input: String;
 
     if let Some(ref cli_input) = args.input {
@@ -119,9 +378,8 @@ fn main() -> Result<(), std::io::Error> {
         return Err(Error::from(ErrorKind::InvalidInput));
     }
 
-    if let Err(e) = parse_args(&args) {
-        return Err(e);
-    };
+    let context = parse_args(&args)?;
+    let storage = parse_storage(&args)?;
 
     let bytecode;
     let input = read_bytecode(input);
@@ -131,10 +389,86 @@ fn main() -> Result<(), std::io::Error> {
         error!("No bytecode found in input");
         return Err(Error::from(io::ErrorKind::InvalidData));
     }
-    let parser = BytecodeParser::new(bytecode);
-    let mut flow_parser = FlowParser::new(parser.get_instructions());
-    flow_parser.parse_flows();
-    warn!("{} flows found.", flow_parser.flows().len());
+    let parser = BytecodeParser::new(bytecode, args.evm_version)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    debug!(
+        "{} instruction sets found.",
+        parser.get_instruction_sets().len()
+    );
+    if let Some(Command::Disasm) = args.command {
+        return disasm::write_listing(
+            &mut io::stdout().lock(),
+            parser.get_instructions(),
+            &parser.get_instruction_sets(),
+            parser.get_metadata(),
+        );
+    }
+    if args.command.is_none() {
+        if let Some(deployment) = Deployment::find(&parser) {
+            return print_deployment(
+                &deployment,
+                parser.get_bytecode(),
+                args.evm_version,
+                &context,
+                &storage,
+                args.format,
+            );
+        }
+    }
+    let flow_parser = parse_flows(&parser, parser.get_bytecode(), &context, &storage);
+    if args.command.is_none() && args.format == Format::Json {
+        return json::write_json(
+            &mut io::stdout().lock(),
+            &[json::Program {
+                name: "code",
+                parser: &parser,
+                flows: flow_parser.flows(),
+            }],
+        );
+    }
+    if args.command.is_none() && args.format == Format::Dot {
+        let cfg = build_cfg(&parser, flow_parser.flows());
+        return dot::write_dot(
+            &mut io::stdout().lock(),
+            &[dot::Program {
+                name: "code",
+                cfg: &cfg,
+                instructions: parser.get_instructions(),
+            }],
+        );
+    }
+    if args.command.is_none() {
+        print_summary(&parser, flow_parser.flows());
+    }
+    if let Some(Command::Patch {
+        insert,
+        replace,
+        remove,
+    }) = &args.command
+    {
+        let invalid = |e| Error::new(ErrorKind::InvalidData, e);
+        let mut patch = Patch::new(&parser, flow_parser.flows()).map_err(invalid)?;
+        for (offset, code) in insert
+            .iter()
+            .map(parse_patch_code)
+            .collect::<Result<Vec<_>, _>>()?
+        {
+            patch.insert(offset, &code).map_err(invalid)?;
+        }
+        for (offset, code) in replace
+            .iter()
+            .map(parse_patch_code)
+            .collect::<Result<Vec<_>, _>>()?
+        {
+            patch.replace(offset, &code).map_err(invalid)?;
+        }
+        for offset in remove {
+            patch.remove(parse_offset(offset)?).map_err(invalid)?;
+        }
+        let bytecode = patch.apply().map_err(invalid)?;
+        println!("{}", to_hex(&bytecode));
+        return Ok(());
+    }
     for flow in flow_parser.flows() {
         warn!("flow");
         flow.print();
@@ -145,7 +479,7 @@ fn main() -> Result<(), std::io::Error> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{hex::Hex, CALLVALUE};
+    use crate::{hex::Hex, opcode::Hardfork};
 
     fn init() {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -202,6 +536,12 @@ b92915050565b60006101da826100df565b91506101e5836100df565b9250\
         assert!(result.is_none());
     }
     #[test]
+    fn invalid_input_odd_length() {
+        init();
+        let result = super::read_bytecode("600".to_string());
+        assert!(result.is_none());
+    }
+    #[test]
     fn empty_input() {
         init();
         let input = "".to_string();
@@ -213,41 +553,64 @@ b92915050565b60006101da826100df565b91506101e5836100df565b9250\
     fn test_input_callarg() {
         let callvalue = "123";
         let args = super::Args {
+            command: None,
             input: None,
             callvalue: Some(callvalue.to_string()),
             calldata: None,
+            env: None,
+            evm_version: Hardfork::default(),
             filename: None,
+            storage: Vec::new(),
+            format: super::Format::default(),
         };
-        let _ = super::parse_args(&args);
-        let callvalue = unsafe { CALLVALUE.clone() }.unwrap();
-
-        assert_eq!(callvalue.value, Hex(0x7b));
-        assert_eq!(callvalue.size, 1);
+        let context = super::parse_args(&args).unwrap();
+        assert_eq!(context.callvalue, Some(Hex::new(0x7b)));
 
         let input = "256";
         let args = super::Args {
+            command: None,
             input: None,
             callvalue: Some(input.to_string()),
             calldata: None,
+            env: None,
+            evm_version: Hardfork::default(),
             filename: None,
+            storage: Vec::new(),
+            format: super::Format::default(),
         };
-        let _ = super::parse_args(&args);
-        let callvalue = unsafe { CALLVALUE.clone() }.unwrap();
-
-        assert_eq!(callvalue.value, Hex(0x0100));
-        assert_eq!(callvalue.size, 2);
+        let context = super::parse_args(&args).unwrap();
+        assert_eq!(context.callvalue, Some(Hex::new(0x0100)));
 
         let input = "";
         let args = super::Args {
+            command: None,
             input: None,
             callvalue: Some(input.to_string()),
             calldata: None,
+            env: None,
+            evm_version: Hardfork::default(),
             filename: None,
+            storage: Vec::new(),
+            format: super::Format::default(),
         };
-        let _ = super::parse_args(&args);
-        let callvalue = unsafe { CALLVALUE.clone() }.unwrap();
+        assert!(super::parse_args(&args).is_err());
+    }
 
-        assert_eq!(callvalue.value, Hex(0x0100));
-        assert_eq!(callvalue.size, 2);
+    #[test]
+    fn test_input_calldata() {
+        let args = super::Args {
+            command: None,
+            input: None,
+            callvalue: None,
+            calldata: Some("0xa9059cbb".to_string()),
+            env: None,
+            evm_version: Hardfork::default(),
+            filename: None,
+            storage: Vec::new(),
+            format: super::Format::default(),
+        };
+        let context = super::parse_args(&args).unwrap();
+        assert_eq!(context.calldata.size(), 4);
+        assert_eq!(context.callvalue, None);
     }
 }
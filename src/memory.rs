@@ -1,9 +1,42 @@
-use crate::{hex::Hex, stack::StackElement};
+use std::fmt::Display;
+
+use crate::{
+    hex::Hex,
+    stack::{StackElement, Symbol},
+};
+
+// Memory this large costs far more gas than a block can provide, so any access beyond it is
+// treated as failing instead of being allocated.
+pub const MAX_MEMORY_SIZE: usize = 1 << 25;
+
+// Where a byte that was copied into memory was read from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ByteSource {
+    CallData(Hex),
+    Code(Hex),
+    ExtCode { address: Hex, offset: Hex },
+}
+
+impl Display for ByteSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ByteSource::CallData(offset) => write!(f, "calldata[{:#x}]", offset),
+            ByteSource::Code(offset) => write!(f, "code[{:#x}]", offset),
+            ByteSource::ExtCode { address, offset } => {
+                write!(f, "extcode({:#x})[{:#x}]", address, offset)
+            }
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MemoryElement {
     pub value: Hex,
     pub origin: Option<Hex>,
+    // Set when the byte is part of a symbolic value, making its actual contents unknown.
+    pub symbol: Option<Symbol>,
+    // Set when the byte was copied in from outside of memory by one of the copy instructions.
+    pub source: Option<ByteSource>,
 }
 
 #[derive(Debug, Clone)]
@@ -18,84 +51,198 @@ impl Memory {
         }
     }
 
-    #[cfg(test)]
     pub fn get_contents(&self) -> &Vec<MemoryElement> {
         &self.elements
     }
 
-    pub fn mload(&mut self, offset: Hex) -> MemoryElement {
-        let offset = offset.0 as usize;
-        if self.elements.len() < (offset + 32) {
-            for _ in self.elements.len()..(offset + 32) {
-                self.elements.push(MemoryElement {
-                    value: Hex(0),
-                    origin: None,
-                });
-            }
+    // Size of the active memory in bytes, as reported by MSIZE. This is always a multiple of
+    // 32 bytes.
+    pub fn size(&self) -> usize {
+        self.elements.len()
+    }
+
+    // Expands memory so that `size` bytes starting at `offset` can be accessed, rounding the
+    // new size up to whole words like the EVM does. Accessing zero bytes never expands memory.
+    // Returns the offset as an index into memory, or an error when the access is beyond
+    // anything the gas limit would allow.
+    fn expand(&mut self, offset: Hex, size: usize) -> Result<usize, ()> {
+        if size == 0 {
+            return Ok(0);
+        }
+        let offset = usize::try_from(offset)?;
+        let end = offset.checked_add(size).ok_or(())?;
+        if end > MAX_MEMORY_SIZE {
+            return Err(());
+        }
+        let new_size = end.div_ceil(32) * 32;
+        while self.elements.len() < new_size {
+            self.elements.push(MemoryElement {
+                value: Hex::new(0),
+                origin: None,
+                symbol: None,
+                source: None,
+            });
         }
+        Ok(offset)
+    }
+
+    pub fn mload(&mut self, offset: Hex) -> Result<MemoryElement, ()> {
+        let offset = self.expand(offset, 32)?;
         let mut result: MemoryElement = MemoryElement {
-            value: Hex(0),
+            value: Hex::new(0),
             origin: None,
+            symbol: None,
+            source: None,
         };
-        for i in offset..(offset + 32) {
-            let element = self.elements.get(i).unwrap();
-            result.value = result.value << Hex(8);
+        for element in &self.elements[offset..(offset + 32)] {
+            result.value = result.value << Hex::new(8);
             result.value += element.value;
             if let Some(origin) = element.origin {
                 result.origin = Some(origin);
             }
-        }
-        result
-    }
-
-    pub fn mstore(&mut self, element: StackElement, offset: Hex, index: Hex) {
-        let offset = offset.0 as usize;
-        // ensure the memory array is lengthy enough.
-        while self.elements.len() < (offset + 32) {
-            for _ in 0..32 {
-                self.elements.push(MemoryElement {
-                    value: Hex(0),
-                    origin: None,
-                });
+            if element.symbol.is_some() {
+                result.symbol = element.symbol.clone();
             }
         }
+        Ok(result)
+    }
+
+    pub fn mstore(&mut self, element: StackElement, offset: Hex, index: Hex) -> Result<(), ()> {
+        let offset = self.expand(offset, 32)?;
 
-        // prepent the new value with enough 0's
-        for i in offset..((offset + 32) - element.size) {
-            self.elements[i] = MemoryElement {
-                value: Hex(0),
-                origin: Some(index),
+        // Bytes beyond the pushed size of the value are padding added by this store, unless the
+        // value grew larger through arithmetic.
+        let size = element.size.max(element.value.byte_len()).min(32);
+        for (i, byte) in element.value.to_be_bytes().iter().enumerate() {
+            self.elements[offset + i] = MemoryElement {
+                value: Hex::from(*byte),
+                origin: if i < 32 - size {
+                    Some(index)
+                } else {
+                    Some(element.origin)
+                },
+                symbol: element.symbol.clone(),
+                source: None,
             };
         }
+        Ok(())
+    }
 
-        // Store the new value.
-        let mut value = element.value;
-        for i in (((offset + 32) - element.size)..(offset + 32)).rev() {
-            let tmp_value = value >> Hex(8);
-            let hex = value & Hex(0xff);
-            value = tmp_value;
-            self.elements[i] = MemoryElement {
-                value: hex,
-                origin: Some(element.origin),
-            };
+    // Stores the least significant byte of the element.
+    pub fn mstore8(&mut self, element: StackElement, offset: Hex) -> Result<(), ()> {
+        let offset = self.expand(offset, 1)?;
+        self.elements[offset] = MemoryElement {
+            value: Hex::from(element.value.to_be_bytes()[31]),
+            origin: Some(element.origin),
+            symbol: element.symbol,
+            source: None,
+        };
+        Ok(())
+    }
+
+    // Returns `size` bytes starting at `offset`, expanding the memory when needed.
+    pub fn read_bytes(&mut self, offset: Hex, size: Hex) -> Result<Vec<MemoryElement>, ()> {
+        let size = usize::try_from(size)?;
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+        let offset = self.expand(offset, size)?;
+        Ok(self.elements[offset..offset + size].to_vec())
+    }
+
+    // Writes the bytes to memory starting at `offset`, expanding the memory when needed.
+    pub fn write_bytes(&mut self, offset: Hex, bytes: Vec<MemoryElement>) -> Result<(), ()> {
+        let offset = self.expand(offset, bytes.len())?;
+        for (i, byte) in bytes.into_iter().enumerate() {
+            self.elements[offset + i] = byte;
         }
+        Ok(())
     }
+}
 
-    pub fn sload(&self, offset: Hex) -> StackElement {
-        let mut result: StackElement = StackElement {
-            value: Hex(0),
-            origin: Hex(0),
+#[cfg(test)]
+mod tests {
+    use crate::{hex::Hex, stack::StackElement};
+
+    use super::{Memory, MAX_MEMORY_SIZE};
+
+    fn element(value: Hex, origin: Hex) -> StackElement {
+        StackElement {
+            value,
+            origin,
             size: 32,
-        };
-        let offset = offset.0 as usize;
-        for i in offset..(offset + 32) {
-            let element = self.elements.get(i).unwrap();
-            result.value = result.value << Hex(8);
-            result.value += element.value;
-            if let Some(origin) = element.origin {
-                result.origin = origin;
-            }
+            symbol: None,
         }
-        result
+    }
+
+    #[test]
+    fn expands_to_whole_words() {
+        let mut memory = Memory::new();
+        memory.mload(Hex::new(0x21)).unwrap();
+        assert_eq!(memory.size(), 0x60);
+        memory.mload(Hex::new(0)).unwrap();
+        assert_eq!(memory.size(), 0x60);
+        memory
+            .mstore(
+                element(Hex::new(1), Hex::new(0)),
+                Hex::new(0x60),
+                Hex::new(1),
+            )
+            .unwrap();
+        assert_eq!(memory.size(), 0x80);
+    }
+
+    #[test]
+    fn zero_sized_read_does_not_expand() {
+        let mut memory = Memory::new();
+        let bytes = memory.read_bytes(Hex::MAX, Hex::new(0)).unwrap();
+        assert!(bytes.is_empty());
+        assert_eq!(memory.size(), 0);
+    }
+
+    #[test]
+    fn out_of_range_access_is_an_error() {
+        let mut memory = Memory::new();
+        assert!(memory.mload(Hex::MAX).is_err());
+        assert!(memory.mload(Hex::ONE << 64).is_err());
+        assert!(memory
+            .read_bytes(Hex::new(0), Hex::new(MAX_MEMORY_SIZE as u128 + 1))
+            .is_err());
+        assert_eq!(memory.size(), 0);
+    }
+
+    #[test]
+    fn mstore8_stores_lowest_byte() {
+        let mut memory = Memory::new();
+        memory
+            .mstore8(element(Hex::new(0x1234), Hex::new(3)), Hex::new(0x22))
+            .unwrap();
+        assert_eq!(memory.size(), 0x40);
+        let word = memory.mload(Hex::new(0x20)).unwrap();
+        assert_eq!(word.value, Hex::new(0x34) << 232);
+        assert_eq!(word.origin, Some(Hex::new(3)));
+    }
+
+    #[test]
+    fn read_across_word_boundary() {
+        let mut memory = Memory::new();
+        memory
+            .mstore(
+                element(Hex::new(0xaabb), Hex::new(0)),
+                Hex::new(0),
+                Hex::new(1),
+            )
+            .unwrap();
+        memory
+            .mstore(
+                element(Hex::new(0xccdd) << 240, Hex::new(2)),
+                Hex::new(0x20),
+                Hex::new(3),
+            )
+            .unwrap();
+        let word = memory.mload(Hex::new(0x1e)).unwrap();
+        assert_eq!(word.value, Hex::new(0xaabbccdd) << 224);
+        assert_eq!(word.origin, Some(Hex::new(2)));
+        assert_eq!(memory.size(), 0x40);
     }
 }
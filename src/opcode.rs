@@ -3,7 +3,7 @@ use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::ToPrimitive;
 use std::collections::HashMap;
 
-use crate::{hex::Hex, instruction::JumpInstruction};
+use crate::instruction::JumpInstruction;
 
 #[derive(PartialEq)]
 pub enum OpCodeResult {
@@ -13,28 +13,34 @@ pub enum OpCodeResult {
     Ok,
 }
 
-trait Parse {
-    fn parse(stack: Vec<Hex>, instruction_pointer: usize) -> (Vec<Hex>, usize);
-}
-
 #[derive(Clone, Eq, PartialEq, Hash)]
 pub struct OpCode {
     pub code: OpCodes,
+    // Number of immediate bytes following the opcode, like the 2 bytes pushed by PUSH2.
     pub input_arguments: u32,
 
-    // SWAP1 is 1, SWAP5 is 5 etc
+    // SWAP1 is 1, SWAP5 is 5 etc, and likewise for DUP and LOG.
     pub operator_index: usize,
 
     pub stack_inputs: u32,
     pub stack_outputs: u32,
+    // The fixed part of the gas cost, without dynamic costs like memory expansion or cold
+    // account access.
+    pub static_gas: u32,
+    // Ends execution, successfully or not.
+    pub halts: bool,
+    pub jumps: bool,
+    // Reads the world state or the environment of the transaction, which makes code impure.
+    pub reads_state: bool,
+    // Modifies the world state, or may do so, which rules out static calls.
+    pub writes_state: bool,
+    // Legacy code can use this opcode, but EOF code cannot.
+    pub legacy_only: bool,
+    // EOF code can use this opcode, but legacy code cannot.
+    pub eof_only: bool,
+    // The first hardfork that has this opcode.
+    pub introduced_in: Hardfork,
     pub short_name: String,
-    //pub parse: fn(
-    //    bytecode: OpCodes:: &HashMap<Hex, Instruction>,
-    //    instruction: &Instruction,
-    //    pc: &mut Hex,
-    //    stack: &mut Vec<Hex>,
-    //    memory: &mut Vec<Hex>,
-    //) -> Result<ParsedInstruction, Error>,
 }
 
 impl fmt::Debug for OpCode {
@@ -51,1219 +57,285 @@ impl Default for OpCode {
             operator_index: Default::default(),
             stack_inputs: Default::default(),
             stack_outputs: Default::default(),
-            short_name: Default::default(),
+            static_gas: Default::default(),
+            halts: true,
+            jumps: false,
+            reads_state: false,
+            writes_state: false,
+            legacy_only: false,
+            eof_only: false,
+            introduced_in: Hardfork::Frontier,
+            short_name: "INVALID".to_string(),
         }
     }
 }
 
-pub fn opcodes() -> HashMap<OpCodes, OpCode> {
-    let mut map = HashMap::new();
-    map.insert(
-        OpCodes::STOP,
-        OpCode {
-            code: OpCodes::STOP,
-            short_name: "STOP".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::ADD,
-        OpCode {
-            code: OpCodes::ADD,
-            short_name: "ADD".to_string(),
-            stack_inputs: 2,
-            stack_outputs: 1,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::MUL,
-        OpCode {
-            code: OpCodes::MUL,
-            short_name: "MUL".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SUB,
-        OpCode {
-            code: OpCodes::SUB,
-            short_name: "SUB".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DIV,
-        OpCode {
-            code: OpCodes::DIV,
-            short_name: "DIV".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SDIV,
-        OpCode {
-            code: OpCodes::SDIV,
-            short_name: "SDIV".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::MOD,
-        OpCode {
-            code: OpCodes::MOD,
-            short_name: "MOD".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SMOD,
-        OpCode {
-            code: OpCodes::SMOD,
-            short_name: "SMOD".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::ADDMOD,
-        OpCode {
-            code: OpCodes::ADDMOD,
-            short_name: "ADDMOD".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::MULMOD,
-        OpCode {
-            code: OpCodes::MULMOD,
-            short_name: "MULMOD".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::EXP,
-        OpCode {
-            code: OpCodes::EXP,
-            short_name: "EXP".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SIGNEXTEND,
-        OpCode {
-            code: OpCodes::SIGNEXTEND,
-            short_name: "SIGNEXTEND".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::LT,
-        OpCode {
-            code: OpCodes::LT,
-            short_name: "LT".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::GT,
-        OpCode {
-            code: OpCodes::GT,
-            short_name: "GT".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SLT,
-        OpCode {
-            code: OpCodes::SLT,
-            short_name: "SLT".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SGT,
-        OpCode {
-            code: OpCodes::SGT,
-            short_name: "SGT".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::EQ,
-        OpCode {
-            code: OpCodes::EQ,
-            short_name: "EQ".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::ISZERO,
-        OpCode {
-            code: OpCodes::ISZERO,
-            short_name: "ISZERO".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::AND,
-        OpCode {
-            code: OpCodes::AND,
-            short_name: "AND".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::OR,
-        OpCode {
-            code: OpCodes::OR,
-            short_name: "OR".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::XOR,
-        OpCode {
-            code: OpCodes::XOR,
-            short_name: "XOR".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::NOT,
-        OpCode {
-            code: OpCodes::NOT,
-            short_name: "NOT".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::BYTE,
-        OpCode {
-            code: OpCodes::BYTE,
-            short_name: "BYTE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SHL,
-        OpCode {
-            code: OpCodes::SHL,
-            short_name: "SHL".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SHR,
-        OpCode {
-            code: OpCodes::SHR,
-            short_name: "SHR".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SAR,
-        OpCode {
-            code: OpCodes::SAR,
-            short_name: "SAR".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SHA3,
-        OpCode {
-            code: OpCodes::SHA3,
-            short_name: "SHA3".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::ADDRESS,
-        OpCode {
-            code: OpCodes::ADDRESS,
-            short_name: "ADDRESS".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::BALANCE,
-        OpCode {
-            code: OpCodes::BALANCE,
-            short_name: "BALANCE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::ORIGIN,
-        OpCode {
-            code: OpCodes::ORIGIN,
-            short_name: "ORIGIN".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CALLER,
-        OpCode {
-            code: OpCodes::CALLER,
-            short_name: "CALLER".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CALLVALUE,
-        OpCode {
-            code: OpCodes::CALLVALUE,
-            short_name: "CALLVALUE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CALLDATALOAD,
-        OpCode {
-            code: OpCodes::CALLDATALOAD,
-            short_name: "CALLDATALOAD".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CALLDATASIZE,
-        OpCode {
-            code: OpCodes::CALLDATASIZE,
-            short_name: "CALLDATASIZE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CALLDATACOPY,
-        OpCode {
-            code: OpCodes::CALLDATACOPY,
-            short_name: "CALLDATACOPY".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CODESIZE,
-        OpCode {
-            code: OpCodes::CODESIZE,
-            short_name: "CODESIZE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CODECOPY,
-        OpCode {
-            code: OpCodes::CODECOPY,
-            short_name: "CODECOPY".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::GASPRICE,
-        OpCode {
-            code: OpCodes::GASPRICE,
-            short_name: "GASPRICE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::EXTCODESIZE,
-        OpCode {
-            code: OpCodes::EXTCODESIZE,
-            short_name: "EXTCODESIZE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::EXTCODECOPY,
-        OpCode {
-            code: OpCodes::EXTCODECOPY,
-            short_name: "EXTCODECOPY".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::RETURNDATASIZE,
-        OpCode {
-            code: OpCodes::RETURNDATASIZE,
-            short_name: "RETURNDATASIZE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::RETURNDATACOPY,
-        OpCode {
-            code: OpCodes::RETURNDATACOPY,
-            short_name: "RETURNDATACOPY".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::EXTCODEHASH,
-        OpCode {
-            code: OpCodes::EXTCODEHASH,
-            short_name: "EXTCODEHASH".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::BLOCKHASH,
-        OpCode {
-            code: OpCodes::BLOCKHASH,
-            short_name: "BLOCKHASH".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::COINBASE,
-        OpCode {
-            code: OpCodes::COINBASE,
-            short_name: "COINBASE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::TIMESTAMP,
-        OpCode {
-            code: OpCodes::TIMESTAMP,
-            short_name: "TIMESTAMP".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::NUMBER,
-        OpCode {
-            code: OpCodes::NUMBER,
-            short_name: "NUMBER".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DIFFICULTY,
-        OpCode {
-            code: OpCodes::DIFFICULTY,
-            short_name: "DIFFICULTY".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::GASLIMIT,
-        OpCode {
-            code: OpCodes::GASLIMIT,
-            short_name: "GASLIMIT".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CHAINID,
-        OpCode {
-            code: OpCodes::CHAINID,
-            short_name: "CHAINID".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SELFBALANCE,
-        OpCode {
-            code: OpCodes::SELFBALANCE,
-            short_name: "SELFBALANCE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::BASEFEE,
-        OpCode {
-            code: OpCodes::BASEFEE,
-            short_name: "BASEFEE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::POP,
-        OpCode {
-            code: OpCodes::POP,
-            short_name: "POP".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::MLOAD,
-        OpCode {
-            code: OpCodes::MLOAD,
-            short_name: "MLOAD".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::MSTORE,
-        OpCode {
-            code: OpCodes::MSTORE,
-            short_name: "MSTORE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::MSTORE8,
-        OpCode {
-            code: OpCodes::MSTORE8,
-            short_name: "MSTORE8".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SLOAD,
-        OpCode {
-            code: OpCodes::SLOAD,
-            short_name: "SLOAD".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SSTORE,
-        OpCode {
-            code: OpCodes::SSTORE,
-            short_name: "SSTORE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::JUMP,
-        OpCode {
-            code: OpCodes::JUMP,
-            short_name: "JUMP".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::JUMPI,
-        OpCode {
-            code: OpCodes::JUMPI,
-            short_name: "JUMPI".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PC,
-        OpCode {
-            code: OpCodes::PC,
-            short_name: "PC".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::MSIZE,
-        OpCode {
-            code: OpCodes::MSIZE,
-            short_name: "MSIZE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::GAS,
-        OpCode {
-            code: OpCodes::GAS,
-            short_name: "GAS".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::JUMPDEST,
-        OpCode {
-            code: OpCodes::JUMPDEST,
-            short_name: "JUMPDEST".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH1,
-        OpCode {
-            code: OpCodes::PUSH1,
-            short_name: "PUSH1".to_string(),
-            input_arguments: 1,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH2,
-        OpCode {
-            code: OpCodes::PUSH2,
-            short_name: "PUSH2".to_string(),
-            input_arguments: 2,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH3,
-        OpCode {
-            code: OpCodes::PUSH3,
-            short_name: "PUSH3".to_string(),
-            input_arguments: 3,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH4,
-        OpCode {
-            code: OpCodes::PUSH4,
-            short_name: "PUSH4".to_string(),
-            input_arguments: 4,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH5,
-        OpCode {
-            code: OpCodes::PUSH5,
-            short_name: "PUSH5".to_string(),
-            input_arguments: 5,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH6,
-        OpCode {
-            code: OpCodes::PUSH6,
-            short_name: "PUSH6".to_string(),
-            input_arguments: 6,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH7,
-        OpCode {
-            code: OpCodes::PUSH7,
-            short_name: "PUSH7".to_string(),
-            input_arguments: 7,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH8,
-        OpCode {
-            code: OpCodes::PUSH8,
-            short_name: "PUSH8".to_string(),
-            input_arguments: 8,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH9,
-        OpCode {
-            code: OpCodes::PUSH9,
-            short_name: "PUSH9".to_string(),
-            input_arguments: 9,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH10,
-        OpCode {
-            code: OpCodes::PUSH10,
-            short_name: "PUSH10".to_string(),
-            input_arguments: 10,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH11,
-        OpCode {
-            code: OpCodes::PUSH11,
-            short_name: "PUSH11".to_string(),
-            input_arguments: 11,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH12,
-        OpCode {
-            code: OpCodes::PUSH12,
-            short_name: "PUSH12".to_string(),
-            input_arguments: 12,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH13,
-        OpCode {
-            code: OpCodes::PUSH13,
-            short_name: "PUSH13".to_string(),
-            input_arguments: 13,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH14,
-        OpCode {
-            code: OpCodes::PUSH14,
-            short_name: "PUSH14".to_string(),
-            input_arguments: 14,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH15,
-        OpCode {
-            code: OpCodes::PUSH15,
-            short_name: "PUSH15".to_string(),
-            input_arguments: 15,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH16,
-        OpCode {
-            code: OpCodes::PUSH16,
-            short_name: "PUSH16".to_string(),
-            input_arguments: 16,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH17,
-        OpCode {
-            code: OpCodes::PUSH17,
-            short_name: "PUSH17".to_string(),
-            input_arguments: 17,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH18,
-        OpCode {
-            code: OpCodes::PUSH18,
-            short_name: "PUSH18".to_string(),
-            input_arguments: 18,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH19,
-        OpCode {
-            code: OpCodes::PUSH19,
-            short_name: "PUSH19".to_string(),
-            input_arguments: 19,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH20,
-        OpCode {
-            code: OpCodes::PUSH20,
-            short_name: "PUSH20".to_string(),
-            input_arguments: 20,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH21,
-        OpCode {
-            code: OpCodes::PUSH21,
-            short_name: "PUSH21".to_string(),
-            input_arguments: 21,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH22,
-        OpCode {
-            code: OpCodes::PUSH22,
-            short_name: "PUSH22".to_string(),
-            input_arguments: 22,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH23,
-        OpCode {
-            code: OpCodes::PUSH23,
-            short_name: "PUSH23".to_string(),
-            input_arguments: 23,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH24,
-        OpCode {
-            code: OpCodes::PUSH24,
-            short_name: "PUSH24".to_string(),
-            input_arguments: 24,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH25,
-        OpCode {
-            code: OpCodes::PUSH25,
-            short_name: "PUSH25".to_string(),
-            input_arguments: 25,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH26,
-        OpCode {
-            code: OpCodes::PUSH26,
-            short_name: "PUSH26".to_string(),
-            input_arguments: 26,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH27,
-        OpCode {
-            code: OpCodes::PUSH27,
-            short_name: "PUSH27".to_string(),
-            input_arguments: 27,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH28,
-        OpCode {
-            code: OpCodes::PUSH28,
-            short_name: "PUSH28".to_string(),
-            input_arguments: 28,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH29,
-        OpCode {
-            code: OpCodes::PUSH29,
-            short_name: "PUSH29".to_string(),
-            input_arguments: 29,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH30,
-        OpCode {
-            code: OpCodes::PUSH30,
-            short_name: "PUSH30".to_string(),
-            input_arguments: 30,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH31,
-        OpCode {
-            code: OpCodes::PUSH31,
-            short_name: "PUSH31".to_string(),
-            input_arguments: 31,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::PUSH32,
-        OpCode {
-            code: OpCodes::PUSH32,
-            short_name: "PUSH32".to_string(),
-            input_arguments: 32,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP1,
-        OpCode {
-            code: OpCodes::DUP1,
-            short_name: "DUP1".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP2,
-        OpCode {
-            code: OpCodes::DUP2,
-            short_name: "DUP2".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP3,
-        OpCode {
-            code: OpCodes::DUP3,
-            short_name: "DUP3".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP4,
-        OpCode {
-            code: OpCodes::DUP4,
-            short_name: "DUP4".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP5,
-        OpCode {
-            code: OpCodes::DUP5,
-            short_name: "DUP5".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP6,
-        OpCode {
-            code: OpCodes::DUP6,
-            short_name: "DUP6".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP7,
-        OpCode {
-            code: OpCodes::DUP7,
-            short_name: "DUP7".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP8,
-        OpCode {
-            code: OpCodes::DUP8,
-            short_name: "DUP8".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP9,
-        OpCode {
-            code: OpCodes::DUP9,
-            short_name: "DUP9".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP10,
-        OpCode {
-            code: OpCodes::DUP10,
-            short_name: "DUP10".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP11,
-        OpCode {
-            code: OpCodes::DUP11,
-            short_name: "DUP11".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP12,
-        OpCode {
-            code: OpCodes::DUP12,
-            short_name: "DUP12".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP13,
-        OpCode {
-            code: OpCodes::DUP13,
-            short_name: "DUP13".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP14,
-        OpCode {
-            code: OpCodes::DUP14,
-            short_name: "DUP14".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP15,
-        OpCode {
-            code: OpCodes::DUP15,
-            short_name: "DUP15".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DUP16,
-        OpCode {
-            code: OpCodes::DUP16,
-            short_name: "DUP16".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP1,
-        OpCode {
-            code: OpCodes::SWAP1,
-            short_name: "SWAP1".to_string(),
-            operator_index: 1,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP2,
-        OpCode {
-            code: OpCodes::SWAP2,
-            short_name: "SWAP2".to_string(),
-            operator_index: 2,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP3,
-        OpCode {
-            code: OpCodes::SWAP3,
-            short_name: "SWAP3".to_string(),
-            operator_index: 3,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP4,
-        OpCode {
-            code: OpCodes::SWAP4,
-            short_name: "SWAP4".to_string(),
-            operator_index: 4,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP5,
-        OpCode {
-            code: OpCodes::SWAP5,
-            short_name: "SWAP5".to_string(),
-            operator_index: 5,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP6,
-        OpCode {
-            code: OpCodes::SWAP6,
-            short_name: "SWAP6".to_string(),
-            operator_index: 6,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP7,
-        OpCode {
-            code: OpCodes::SWAP7,
-            short_name: "SWAP7".to_string(),
-            operator_index: 7,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP8,
-        OpCode {
-            code: OpCodes::SWAP8,
-            short_name: "SWAP8".to_string(),
-            operator_index: 8,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP9,
-        OpCode {
-            code: OpCodes::SWAP9,
-            short_name: "SWAP9".to_string(),
-            operator_index: 9,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP10,
-        OpCode {
-            code: OpCodes::SWAP10,
-            short_name: "SWAP10".to_string(),
-            operator_index: 10,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP11,
-        OpCode {
-            code: OpCodes::SWAP11,
-            short_name: "SWAP11".to_string(),
-            operator_index: 11,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP12,
-        OpCode {
-            code: OpCodes::SWAP12,
-            short_name: "SWAP12".to_string(),
-            operator_index: 12,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP13,
-        OpCode {
-            code: OpCodes::SWAP13,
-            short_name: "SWAP13".to_string(),
-            operator_index: 13,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP14,
-        OpCode {
-            code: OpCodes::SWAP14,
-            short_name: "SWAP14".to_string(),
-            operator_index: 14,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP15,
-        OpCode {
-            code: OpCodes::SWAP15,
-            short_name: "SWAP15".to_string(),
-            operator_index: 15,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::SWAP16,
-        OpCode {
-            code: OpCodes::SWAP16,
-            short_name: "SWAP16".to_string(),
-            operator_index: 16,
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::LOG0,
-        OpCode {
-            code: OpCodes::LOG0,
-            short_name: "LOG0".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::LOG1,
-        OpCode {
-            code: OpCodes::LOG1,
-            short_name: "LOG1".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::LOG2,
-        OpCode {
-            code: OpCodes::LOG2,
-            short_name: "LOG2".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::LOG3,
-        OpCode {
-            code: OpCodes::LOG3,
-            short_name: "LOG3".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::LOG4,
-        OpCode {
-            code: OpCodes::LOG4,
-            short_name: "LOG4".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CREATE,
-        OpCode {
-            code: OpCodes::CREATE,
-            short_name: "CREATE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CALL,
-        OpCode {
-            code: OpCodes::CALL,
-            short_name: "CALL".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CALLCODE,
-        OpCode {
-            code: OpCodes::CALLCODE,
-            short_name: "CALLCODE".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::RETURN,
-        OpCode {
-            code: OpCodes::RETURN,
-            short_name: "RETURN".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::DELEGATECALL,
-        OpCode {
-            code: OpCodes::DELEGATECALL,
-            short_name: "DELEGATECALL".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::CREATE2,
-        OpCode {
-            code: OpCodes::CREATE2,
-            short_name: "CALLBLACKBOX".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::STATICCALL,
-        OpCode {
-            code: OpCodes::STATICCALL,
-            short_name: "STATICCALL".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::REVERT,
-        OpCode {
-            code: OpCodes::REVERT,
-            short_name: "REVERT".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::INVALID,
-        OpCode {
-            code: OpCodes::INVALID,
-            short_name: "INVALID".to_string(),
-            ..Default::default()
-        },
-    );
-    map.insert(
-        OpCodes::EOFMAGIC,
-        OpCode {
-            code: OpCodes::EOFMAGIC,
-            short_name: "EOFMAGIC".to_string(),
-            ..Default::default()
-        },
-    );
+// EVM versions that changed the instruction set, in the order they were activated. The names
+// on the command line follow solc's `--evm-version`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, clap::ValueEnum)]
+#[value(rename_all = "camelCase")]
+pub enum Hardfork {
+    Frontier,
+    Homestead,
+    TangerineWhistle,
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    Petersburg,
+    Istanbul,
+    Berlin,
+    London,
+    Paris,
+    Shanghai,
+    #[default]
+    Cancun,
+    Prague,
+}
+
+const NONE: u8 = 0;
+const HALTS: u8 = 1;
+const JUMPS: u8 = 1 << 1;
+const READS_STATE: u8 = 1 << 2;
+const WRITES_STATE: u8 = 1 << 3;
+// Opcodes that EOF code may not use, because they inspect the code or jump to computed targets.
+const LEGACY_ONLY: u8 = 1 << 4;
+// Opcodes that only exist in EOF code.
+const EOF_ONLY: u8 = 1 << 5;
 
-    map.insert(
-        OpCodes::SELFDESTRUCT,
-        OpCode {
-            code: OpCodes::SELFDESTRUCT,
-            short_name: "SELFDESTRUCT".to_string(),
-            ..Default::default()
-        },
-    );
-    map
+// The single source of all opcode metadata, one row per opcode:
+// (opcode, immediate bytes, stack inputs, stack outputs, static gas, flags, introduced in)
+#[rustfmt::skip]
+const OPCODE_TABLE: [(OpCodes, u32, u32, u32, u32, u8, Hardfork); 163] = {
+    use Hardfork::*;
+    use OpCodes::*;
+    [
+        (STOP, 0, 0, 0, 0, HALTS, Frontier),
+        (ADD, 0, 2, 1, 3, NONE, Frontier),
+        (MUL, 0, 2, 1, 5, NONE, Frontier),
+        (SUB, 0, 2, 1, 3, NONE, Frontier),
+        (DIV, 0, 2, 1, 5, NONE, Frontier),
+        (SDIV, 0, 2, 1, 5, NONE, Frontier),
+        (MOD, 0, 2, 1, 5, NONE, Frontier),
+        (SMOD, 0, 2, 1, 5, NONE, Frontier),
+        (ADDMOD, 0, 3, 1, 8, NONE, Frontier),
+        (MULMOD, 0, 3, 1, 8, NONE, Frontier),
+        (EXP, 0, 2, 1, 10, NONE, Frontier),
+        (SIGNEXTEND, 0, 2, 1, 5, NONE, Frontier),
+        (LT, 0, 2, 1, 3, NONE, Frontier),
+        (GT, 0, 2, 1, 3, NONE, Frontier),
+        (SLT, 0, 2, 1, 3, NONE, Frontier),
+        (SGT, 0, 2, 1, 3, NONE, Frontier),
+        (EQ, 0, 2, 1, 3, NONE, Frontier),
+        (ISZERO, 0, 1, 1, 3, NONE, Frontier),
+        (AND, 0, 2, 1, 3, NONE, Frontier),
+        (OR, 0, 2, 1, 3, NONE, Frontier),
+        (XOR, 0, 2, 1, 3, NONE, Frontier),
+        (NOT, 0, 1, 1, 3, NONE, Frontier),
+        (BYTE, 0, 2, 1, 3, NONE, Frontier),
+        (SHL, 0, 2, 1, 3, NONE, Constantinople),
+        (SHR, 0, 2, 1, 3, NONE, Constantinople),
+        (SAR, 0, 2, 1, 3, NONE, Constantinople),
+        (SHA3, 0, 2, 1, 30, NONE, Frontier),
+        (ADDRESS, 0, 0, 1, 2, READS_STATE, Frontier),
+        (BALANCE, 0, 1, 1, 100, READS_STATE, Frontier),
+        (ORIGIN, 0, 0, 1, 2, READS_STATE, Frontier),
+        (CALLER, 0, 0, 1, 2, READS_STATE, Frontier),
+        (CALLVALUE, 0, 0, 1, 2, READS_STATE, Frontier),
+        (CALLDATALOAD, 0, 1, 1, 3, NONE, Frontier),
+        (CALLDATASIZE, 0, 0, 1, 2, NONE, Frontier),
+        (CALLDATACOPY, 0, 3, 0, 3, NONE, Frontier),
+        (CODESIZE, 0, 0, 1, 2, LEGACY_ONLY, Frontier),
+        (CODECOPY, 0, 3, 0, 3, LEGACY_ONLY, Frontier),
+        (GASPRICE, 0, 0, 1, 2, READS_STATE, Frontier),
+        (EXTCODESIZE, 0, 1, 1, 100, READS_STATE | LEGACY_ONLY, Frontier),
+        (EXTCODECOPY, 0, 4, 0, 100, READS_STATE | LEGACY_ONLY, Frontier),
+        (RETURNDATASIZE, 0, 0, 1, 2, NONE, Byzantium),
+        (RETURNDATACOPY, 0, 3, 0, 3, NONE, Byzantium),
+        (EXTCODEHASH, 0, 1, 1, 100, READS_STATE | LEGACY_ONLY, Constantinople),
+        (BLOCKHASH, 0, 1, 1, 20, READS_STATE, Frontier),
+        (COINBASE, 0, 0, 1, 2, READS_STATE, Frontier),
+        (TIMESTAMP, 0, 0, 1, 2, READS_STATE, Frontier),
+        (NUMBER, 0, 0, 1, 2, READS_STATE, Frontier),
+        (DIFFICULTY, 0, 0, 1, 2, READS_STATE, Frontier),
+        (GASLIMIT, 0, 0, 1, 2, READS_STATE, Frontier),
+        (CHAINID, 0, 0, 1, 2, READS_STATE, Istanbul),
+        (SELFBALANCE, 0, 0, 1, 5, READS_STATE, Istanbul),
+        (BASEFEE, 0, 0, 1, 2, READS_STATE, London),
+        (BLOBHASH, 0, 1, 1, 3, READS_STATE, Cancun),
+        (BLOBBASEFEE, 0, 0, 1, 2, READS_STATE, Cancun),
+        (POP, 0, 1, 0, 2, NONE, Frontier),
+        (MLOAD, 0, 1, 1, 3, NONE, Frontier),
+        (MSTORE, 0, 2, 0, 3, NONE, Frontier),
+        (MSTORE8, 0, 2, 0, 3, NONE, Frontier),
+        (SLOAD, 0, 1, 1, 100, READS_STATE, Frontier),
+        (SSTORE, 0, 2, 0, 100, WRITES_STATE, Frontier),
+        (JUMP, 0, 1, 0, 8, JUMPS | LEGACY_ONLY, Frontier),
+        (JUMPI, 0, 2, 0, 10, JUMPS | LEGACY_ONLY, Frontier),
+        (PC, 0, 0, 1, 2, LEGACY_ONLY, Frontier),
+        (MSIZE, 0, 0, 1, 2, NONE, Frontier),
+        (GAS, 0, 0, 1, 2, READS_STATE | LEGACY_ONLY, Frontier),
+        (JUMPDEST, 0, 0, 0, 1, NONE, Frontier),
+        (TLOAD, 0, 1, 1, 100, READS_STATE, Cancun),
+        (TSTORE, 0, 2, 0, 100, WRITES_STATE, Cancun),
+        (MCOPY, 0, 3, 0, 3, NONE, Cancun),
+        (PUSH0, 0, 0, 1, 2, NONE, Shanghai),
+        (PUSH1, 1, 0, 1, 3, NONE, Frontier),
+        (PUSH2, 2, 0, 1, 3, NONE, Frontier),
+        (PUSH3, 3, 0, 1, 3, NONE, Frontier),
+        (PUSH4, 4, 0, 1, 3, NONE, Frontier),
+        (PUSH5, 5, 0, 1, 3, NONE, Frontier),
+        (PUSH6, 6, 0, 1, 3, NONE, Frontier),
+        (PUSH7, 7, 0, 1, 3, NONE, Frontier),
+        (PUSH8, 8, 0, 1, 3, NONE, Frontier),
+        (PUSH9, 9, 0, 1, 3, NONE, Frontier),
+        (PUSH10, 10, 0, 1, 3, NONE, Frontier),
+        (PUSH11, 11, 0, 1, 3, NONE, Frontier),
+        (PUSH12, 12, 0, 1, 3, NONE, Frontier),
+        (PUSH13, 13, 0, 1, 3, NONE, Frontier),
+        (PUSH14, 14, 0, 1, 3, NONE, Frontier),
+        (PUSH15, 15, 0, 1, 3, NONE, Frontier),
+        (PUSH16, 16, 0, 1, 3, NONE, Frontier),
+        (PUSH17, 17, 0, 1, 3, NONE, Frontier),
+        (PUSH18, 18, 0, 1, 3, NONE, Frontier),
+        (PUSH19, 19, 0, 1, 3, NONE, Frontier),
+        (PUSH20, 20, 0, 1, 3, NONE, Frontier),
+        (PUSH21, 21, 0, 1, 3, NONE, Frontier),
+        (PUSH22, 22, 0, 1, 3, NONE, Frontier),
+        (PUSH23, 23, 0, 1, 3, NONE, Frontier),
+        (PUSH24, 24, 0, 1, 3, NONE, Frontier),
+        (PUSH25, 25, 0, 1, 3, NONE, Frontier),
+        (PUSH26, 26, 0, 1, 3, NONE, Frontier),
+        (PUSH27, 27, 0, 1, 3, NONE, Frontier),
+        (PUSH28, 28, 0, 1, 3, NONE, Frontier),
+        (PUSH29, 29, 0, 1, 3, NONE, Frontier),
+        (PUSH30, 30, 0, 1, 3, NONE, Frontier),
+        (PUSH31, 31, 0, 1, 3, NONE, Frontier),
+        (PUSH32, 32, 0, 1, 3, NONE, Frontier),
+        (DUP1, 0, 1, 2, 3, NONE, Frontier),
+        (DUP2, 0, 2, 3, 3, NONE, Frontier),
+        (DUP3, 0, 3, 4, 3, NONE, Frontier),
+        (DUP4, 0, 4, 5, 3, NONE, Frontier),
+        (DUP5, 0, 5, 6, 3, NONE, Frontier),
+        (DUP6, 0, 6, 7, 3, NONE, Frontier),
+        (DUP7, 0, 7, 8, 3, NONE, Frontier),
+        (DUP8, 0, 8, 9, 3, NONE, Frontier),
+        (DUP9, 0, 9, 10, 3, NONE, Frontier),
+        (DUP10, 0, 10, 11, 3, NONE, Frontier),
+        (DUP11, 0, 11, 12, 3, NONE, Frontier),
+        (DUP12, 0, 12, 13, 3, NONE, Frontier),
+        (DUP13, 0, 13, 14, 3, NONE, Frontier),
+        (DUP14, 0, 14, 15, 3, NONE, Frontier),
+        (DUP15, 0, 15, 16, 3, NONE, Frontier),
+        (DUP16, 0, 16, 17, 3, NONE, Frontier),
+        (SWAP1, 0, 2, 2, 3, NONE, Frontier),
+        (SWAP2, 0, 3, 3, 3, NONE, Frontier),
+        (SWAP3, 0, 4, 4, 3, NONE, Frontier),
+        (SWAP4, 0, 5, 5, 3, NONE, Frontier),
+        (SWAP5, 0, 6, 6, 3, NONE, Frontier),
+        (SWAP6, 0, 7, 7, 3, NONE, Frontier),
+        (SWAP7, 0, 8, 8, 3, NONE, Frontier),
+        (SWAP8, 0, 9, 9, 3, NONE, Frontier),
+        (SWAP9, 0, 10, 10, 3, NONE, Frontier),
+        (SWAP10, 0, 11, 11, 3, NONE, Frontier),
+        (SWAP11, 0, 12, 12, 3, NONE, Frontier),
+        (SWAP12, 0, 13, 13, 3, NONE, Frontier),
+        (SWAP13, 0, 14, 14, 3, NONE, Frontier),
+        (SWAP14, 0, 15, 15, 3, NONE, Frontier),
+        (SWAP15, 0, 16, 16, 3, NONE, Frontier),
+        (SWAP16, 0, 17, 17, 3, NONE, Frontier),
+        (LOG0, 0, 2, 0, 375, WRITES_STATE, Frontier),
+        (LOG1, 0, 3, 0, 750, WRITES_STATE, Frontier),
+        (LOG2, 0, 4, 0, 1125, WRITES_STATE, Frontier),
+        (LOG3, 0, 5, 0, 1500, WRITES_STATE, Frontier),
+        (LOG4, 0, 6, 0, 1875, WRITES_STATE, Frontier),
+        (DATALOAD, 0, 1, 1, 4, EOF_ONLY, Prague),
+        (DATALOADN, 2, 0, 1, 3, EOF_ONLY, Prague),
+        (DATASIZE, 0, 0, 1, 2, EOF_ONLY, Prague),
+        (DATACOPY, 0, 3, 0, 3, EOF_ONLY, Prague),
+        // The stack effects of the following depend on their immediates or on the types of
+        // the code sections they refer to; see eof::stack_effect.
+        (RJUMP, 2, 0, 0, 2, JUMPS | EOF_ONLY, Prague),
+        (RJUMPI, 2, 1, 0, 4, JUMPS | EOF_ONLY, Prague),
+        // The immediate is the number of jump offsets minus one, followed by the offsets.
+        (RJUMPV, 1, 1, 0, 4, JUMPS | EOF_ONLY, Prague),
+        (CALLF, 2, 0, 0, 5, JUMPS | EOF_ONLY, Prague),
+        (RETF, 0, 0, 0, 3, HALTS | EOF_ONLY, Prague),
+        (JUMPF, 2, 0, 0, 5, HALTS | JUMPS | EOF_ONLY, Prague),
+        (DUPN, 1, 0, 0, 3, EOF_ONLY, Prague),
+        (SWAPN, 1, 0, 0, 3, EOF_ONLY, Prague),
+        (EXCHANGE, 1, 0, 0, 3, EOF_ONLY, Prague),
+        (EOFMAGIC, 0, 0, 0, 0, HALTS | LEGACY_ONLY, Frontier),
+        (CREATE, 0, 3, 1, 32000, WRITES_STATE | LEGACY_ONLY, Frontier),
+        (CALL, 0, 7, 1, 100, READS_STATE | WRITES_STATE | LEGACY_ONLY, Frontier),
+        (CALLCODE, 0, 7, 1, 100, READS_STATE | WRITES_STATE | LEGACY_ONLY, Frontier),
+        (RETURN, 0, 2, 0, 0, HALTS, Frontier),
+        (DELEGATECALL, 0, 6, 1, 100, READS_STATE | WRITES_STATE | LEGACY_ONLY, Homestead),
+        (CREATE2, 0, 4, 1, 32000, WRITES_STATE | LEGACY_ONLY, Constantinople),
+        (STATICCALL, 0, 6, 1, 100, READS_STATE | LEGACY_ONLY, Byzantium),
+        (REVERT, 0, 2, 0, 0, HALTS, Byzantium),
+        (INVALID, 0, 0, 0, 0, HALTS, Frontier),
+        (SELFDESTRUCT, 0, 1, 0, 5000, HALTS | WRITES_STATE | LEGACY_ONLY, Frontier),
+    ]
+};
+
+// The immediate size of PUSH0 up to PUSH32, or None for any other opcode.
+pub fn push_width(opcode: &OpCodes) -> Option<usize> {
+    let byte = opcode.clone() as usize;
+    (OpCodes::PUSH0 as usize..=OpCodes::PUSH32 as usize)
+        .contains(&byte)
+        .then(|| byte - OpCodes::PUSH0 as usize)
+}
+
+// Returns the opcodes that legacy code can use in the given hardfork.
+pub fn opcodes_for(hardfork: Hardfork) -> HashMap<OpCodes, OpCode> {
+    opcodes()
+        .into_iter()
+        .filter(|(_, opcode)| opcode.introduced_in <= hardfork && !opcode.eof_only)
+        .collect()
+}
+
+// Returns the opcodes that EOF code can use in the given hardfork.
+pub fn eof_opcodes_for(hardfork: Hardfork) -> HashMap<OpCodes, OpCode> {
+    opcodes()
+        .into_iter()
+        .filter(|(_, opcode)| opcode.introduced_in <= hardfork && !opcode.legacy_only)
+        .collect()
+}
+
+// Returns the opcodes of the latest hardfork.
+pub fn opcodes() -> HashMap<OpCodes, OpCode> {
+    OPCODE_TABLE
+        .iter()
+        .map(|(code, immediate, inputs, outputs, gas, flags, hardfork)| {
+            let short_name = format!("{:?}", code);
+            let operator_index = short_name
+                .strip_prefix("DUP")
+                .or(short_name.strip_prefix("SWAP"))
+                .or(short_name.strip_prefix("LOG"))
+                .and_then(|index| index.parse().ok())
+                .unwrap_or_default();
+            let opcode = OpCode {
+                code: code.clone(),
+                input_arguments: *immediate,
+                operator_index,
+                stack_inputs: *inputs,
+                stack_outputs: *outputs,
+                static_gas: *gas,
+                halts: flags & HALTS != 0,
+                jumps: flags & JUMPS != 0,
+                reads_state: flags & READS_STATE != 0,
+                writes_state: flags & WRITES_STATE != 0,
+                legacy_only: flags & LEGACY_ONLY != 0,
+                eof_only: flags & EOF_ONLY != 0,
+                introduced_in: *hardfork,
+                short_name,
+            };
+            (code.clone(), opcode)
+        })
+        .collect()
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Eq, Hash, PartialEq, FromPrimitive, ToPrimitive)]
 pub enum OpCodes {
     STOP = 0x00,
@@ -1318,6 +390,8 @@ pub enum OpCodes {
     CHAINID = 0x46,
     SELFBALANCE = 0x47,
     BASEFEE = 0x48,
+    BLOBHASH = 0x49,
+    BLOBBASEFEE = 0x4a,
     POP = 0x50,
     MLOAD = 0x51,
     MSTORE = 0x52,
@@ -1330,6 +404,9 @@ pub enum OpCodes {
     MSIZE = 0x59,
     GAS = 0x5a,
     JUMPDEST = 0x5b,
+    TLOAD = 0x5c,
+    TSTORE = 0x5d,
+    MCOPY = 0x5e,
     PUSH0 = 0x5f,
     PUSH1 = 0x60,
     PUSH2 = 0x61,
@@ -1400,6 +477,19 @@ pub enum OpCodes {
     LOG2 = 0xa2,
     LOG3 = 0xa3,
     LOG4 = 0xa4,
+    DATALOAD = 0xd0,
+    DATALOADN = 0xd1,
+    DATASIZE = 0xd2,
+    DATACOPY = 0xd3,
+    RJUMP = 0xe0,
+    RJUMPI = 0xe1,
+    RJUMPV = 0xe2,
+    CALLF = 0xe3,
+    RETF = 0xe4,
+    JUMPF = 0xe5,
+    DUPN = 0xe6,
+    SWAPN = 0xe7,
+    EXCHANGE = 0xe8,
     EOFMAGIC = 0xef,
     CREATE = 0xf0,
     CALL = 0xf1,
@@ -1418,3 +508,91 @@ impl fmt::LowerHex for OpCodes {
         write!(f, "{:x}", ToPrimitive::to_u32(self).unwrap())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{eof_opcodes_for, opcodes, opcodes_for, Hardfork, OpCodes};
+
+    #[test]
+    fn every_opcode_has_metadata() {
+        let opcodes = opcodes();
+        for byte in 0..=255u32 {
+            let code: Option<OpCodes> = num_traits::FromPrimitive::from_u32(byte);
+            if let Some(code) = code {
+                assert!(opcodes.contains_key(&code), "{:?} is missing", code);
+            }
+        }
+    }
+
+    #[test]
+    fn stack_effects() {
+        let opcodes = opcodes();
+        let sub = &opcodes[&OpCodes::SUB];
+        assert_eq!(
+            (sub.stack_inputs, sub.stack_outputs, sub.static_gas),
+            (2, 1, 3)
+        );
+        let dup3 = &opcodes[&OpCodes::DUP3];
+        assert_eq!((dup3.stack_inputs, dup3.stack_outputs), (3, 4));
+        assert_eq!(dup3.operator_index, 3);
+        let swap16 = &opcodes[&OpCodes::SWAP16];
+        assert_eq!((swap16.stack_inputs, swap16.stack_outputs), (17, 17));
+        let log2 = &opcodes[&OpCodes::LOG2];
+        assert_eq!((log2.stack_inputs, log2.static_gas), (4, 1125));
+        assert!(log2.writes_state);
+        assert_eq!(opcodes[&OpCodes::PUSH20].input_arguments, 20);
+        assert_eq!(opcodes[&OpCodes::CALL].stack_inputs, 7);
+    }
+
+    #[test]
+    fn flags() {
+        let opcodes = opcodes();
+        assert!(opcodes[&OpCodes::REVERT].halts);
+        assert!(!opcodes[&OpCodes::JUMP].halts);
+        assert!(opcodes[&OpCodes::JUMPI].jumps);
+        assert!(opcodes[&OpCodes::SLOAD].reads_state);
+        assert!(!opcodes[&OpCodes::SLOAD].writes_state);
+        assert!(opcodes[&OpCodes::CALLER].reads_state);
+        assert!(!opcodes[&OpCodes::CALLDATALOAD].reads_state);
+        assert!(opcodes[&OpCodes::SSTORE].writes_state);
+        assert!(!opcodes[&OpCodes::ADD].reads_state);
+    }
+
+    #[test]
+    fn opcodes_per_hardfork() {
+        let frontier = opcodes_for(Hardfork::Frontier);
+        assert!(frontier.contains_key(&OpCodes::ADD));
+        assert!(frontier.contains_key(&OpCodes::INVALID));
+        assert!(!frontier.contains_key(&OpCodes::DELEGATECALL));
+        assert!(!frontier.contains_key(&OpCodes::SHL));
+
+        let petersburg = opcodes_for(Hardfork::Petersburg);
+        assert!(petersburg.contains_key(&OpCodes::SHL));
+        assert!(petersburg.contains_key(&OpCodes::CREATE2));
+        assert!(!petersburg.contains_key(&OpCodes::CHAINID));
+
+        let shanghai = opcodes_for(Hardfork::Shanghai);
+        assert!(shanghai.contains_key(&OpCodes::PUSH0));
+        assert!(!shanghai.contains_key(&OpCodes::TSTORE));
+
+        let prague = opcodes_for(Hardfork::Prague);
+        assert!(prague.contains_key(&OpCodes::JUMP));
+        assert!(!prague.contains_key(&OpCodes::RJUMP));
+        assert_eq!(
+            prague.len(),
+            opcodes().values().filter(|opcode| !opcode.eof_only).count()
+        );
+    }
+
+    #[test]
+    fn eof_opcodes() {
+        let eof = eof_opcodes_for(Hardfork::Prague);
+        assert!(eof.contains_key(&OpCodes::RJUMPV));
+        assert!(eof.contains_key(&OpCodes::ADD));
+        assert!(!eof.contains_key(&OpCodes::JUMP));
+        assert!(!eof.contains_key(&OpCodes::CODECOPY));
+        assert!(!eof.contains_key(&OpCodes::SELFDESTRUCT));
+        assert_eq!(eof[&OpCodes::DATALOADN].input_arguments, 2);
+        assert!(!eof_opcodes_for(Hardfork::Cancun).contains_key(&OpCodes::RJUMP));
+    }
+}
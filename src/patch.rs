@@ -0,0 +1,367 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use crate::{
+    flow::Flow,
+    hex::Hex,
+    instruction::Instruction,
+    opcode::{push_width, OpCodes},
+    parser::Parser,
+};
+
+// Errors in a patch, which leave the bytecode untouched.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchError {
+    // The offset is not the start of an instruction.
+    NotAnInstruction(Hex),
+    // The instruction at the offset was already removed or replaced.
+    AlreadyReplaced(Hex),
+    // The instruction at the offset is the target of a jump and cannot be removed.
+    RemovedJumpTarget(Hex),
+    // The code is in an EOF container, whose section sizes and relative jumps are not
+    // relocated.
+    EofContainer,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::NotAnInstruction(offset) => {
+                write!(f, "offset {:#x} is not the start of an instruction", offset)
+            }
+            PatchError::AlreadyReplaced(offset) => {
+                write!(f, "instruction at {:#x} is already patched", offset)
+            }
+            PatchError::RemovedJumpTarget(offset) => {
+                write!(f, "instruction at {:#x} is a jump target", offset)
+            }
+            PatchError::EofContainer => write!(f, "EOF code cannot be patched"),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+// Edits the bytecode of a parser while keeping its jumps intact. Inserting or removing code
+// moves every instruction after it, so the PUSH instructions that feed the jumps found in the
+// flows are rewritten to the new offsets, and widened when the new offset no longer fits:
+//
+//     let mut patch = Patch::new(&parser, flow_parser.flows())?;
+//     patch.insert(offset, &assemble("PUSH1 0x01\nPOP")?)?;
+//     let bytecode = patch.apply()?;
+//
+// Jumps to an offset keep landing on the instruction that was there, so code inserted in front
+// of a JUMPDEST only runs when execution falls through to it. To run code whenever a block is
+// entered, insert it after the JUMPDEST.
+//
+// Only jump targets that are pushed as a constant can be relocated. Targets that are computed,
+// and offsets used by CODECOPY, keep their original value. Code in an EOF container is not
+// supported.
+pub struct Patch<'a> {
+    instructions: BTreeMap<Hex, &'a Instruction>,
+    // The bytes after the last instruction, such as the metadata, which are kept as they are.
+    trailer: &'a [u8],
+    end: Hex,
+    // The jump targets by the offset of the PUSH instruction that pushes them.
+    jump_targets: HashMap<Hex, Hex>,
+    insertions: HashMap<Hex, Vec<u8>>,
+    replacements: HashMap<Hex, Vec<u8>>,
+}
+
+impl<'a> Patch<'a> {
+    pub fn new(parser: &'a Parser, flows: &[Flow]) -> Result<Patch<'a>, PatchError> {
+        if parser.get_container().is_some() {
+            return Err(PatchError::EofContainer);
+        }
+        let instructions: BTreeMap<Hex, &Instruction> = parser
+            .get_instructions()
+            .iter()
+            .map(|(offset, instruction)| (*offset, instruction))
+            .collect();
+        let end = instructions
+            .values()
+            .next_back()
+            .map_or(Hex::ZERO, |instruction| {
+                instruction.index + Hex::from(1 + instruction.args.len())
+            });
+        let bytecode = parser.get_bytecode();
+        let trailer = bytecode.get(end.as_usize()..).unwrap_or_default();
+
+        let mut jump_targets = HashMap::new();
+        for jump in flows.iter().flat_map(|flow| flow.jumps()) {
+            let target = &jump.target;
+            let Some(push) = instructions.get(&target.origin) else {
+                continue;
+            };
+            // The origin is the instruction that created the value, which is only the PUSH of
+            // the target itself when the target was not computed from it.
+            if push_width(&push.opcode.code).is_some()
+                && push.push_value() == target.value
+                && instructions.contains_key(&target.value)
+            {
+                jump_targets.insert(push.index, target.value);
+            }
+        }
+
+        Ok(Patch {
+            instructions,
+            trailer,
+            end,
+            jump_targets,
+            insertions: HashMap::new(),
+            replacements: HashMap::new(),
+        })
+    }
+
+    // Inserts code in front of the instruction at `offset`, or at the end of the code when
+    // `offset` is right after the last instruction. Code inserted at the same offset earlier
+    // comes first.
+    pub fn insert(&mut self, offset: Hex, code: &[u8]) -> Result<(), PatchError> {
+        if offset != self.end && !self.instructions.contains_key(&offset) {
+            return Err(PatchError::NotAnInstruction(offset));
+        }
+        self.insertions
+            .entry(offset)
+            .or_default()
+            .extend_from_slice(code);
+        Ok(())
+    }
+
+    // Replaces the instruction at `offset` with `code`. Jumps to the instruction land at the
+    // start of `code`, which should then start with a JUMPDEST.
+    pub fn replace(&mut self, offset: Hex, code: &[u8]) -> Result<(), PatchError> {
+        if !self.instructions.contains_key(&offset) {
+            return Err(PatchError::NotAnInstruction(offset));
+        }
+        if self.replacements.contains_key(&offset) {
+            return Err(PatchError::AlreadyReplaced(offset));
+        }
+        self.replacements.insert(offset, code.to_vec());
+        Ok(())
+    }
+
+    pub fn remove(&mut self, offset: Hex) -> Result<(), PatchError> {
+        if self.jump_targets.values().any(|target| *target == offset) {
+            return Err(PatchError::RemovedJumpTarget(offset));
+        }
+        self.replace(offset, &[])
+    }
+
+    // Returns the patched bytecode.
+    pub fn apply(&self) -> Result<Vec<u8>, PatchError> {
+        // The PUSH instructions that are replaced are not rewritten.
+        let jump_targets: HashMap<Hex, Hex> = self
+            .jump_targets
+            .iter()
+            .filter(|(push, _)| !self.replacements.contains_key(push))
+            .map(|(push, target)| (*push, *target))
+            .collect();
+        for target in jump_targets.values() {
+            if self.replacements.get(target).is_some_and(Vec::is_empty) {
+                return Err(PatchError::RemovedJumpTarget(*target));
+            }
+        }
+
+        // A relocated target can need a wider PUSH, which moves everything after it again.
+        // Widen the pushes until every target fits; they are never narrowed, so the loop stops
+        // once no push has to grow.
+        let mut widths: HashMap<Hex, usize> = jump_targets
+            .keys()
+            .map(|push| (*push, self.instructions[push].args.len()))
+            .collect();
+        let offsets = loop {
+            let offsets = self.relocate(&widths);
+            let mut grown = false;
+            for (push, target) in &jump_targets {
+                let needed = offsets[target].byte_len();
+                let width = widths.get_mut(push).expect("every jump target has a width");
+                if needed > *width {
+                    *width = needed;
+                    grown = true;
+                }
+            }
+            if !grown {
+                break offsets;
+            }
+        };
+
+        let mut bytecode = Vec::new();
+        for (offset, instruction) in &self.instructions {
+            if let Some(code) = self.insertions.get(offset) {
+                bytecode.extend_from_slice(code);
+            }
+            if let Some(code) = self.replacements.get(offset) {
+                bytecode.extend_from_slice(code);
+            } else if let Some(target) = jump_targets.get(offset) {
+                let width = widths[offset];
+                bytecode.push(OpCodes::PUSH0 as u8 + width as u8);
+                bytecode.extend_from_slice(&offsets[target].to_be_bytes()[32 - width..]);
+            } else {
+                bytecode.push(instruction.opcode_byte());
+                bytecode.extend(instruction.args.iter().map(|arg| arg.as_usize() as u8));
+            }
+        }
+        if let Some(code) = self.insertions.get(&self.end) {
+            bytecode.extend_from_slice(code);
+        }
+        bytecode.extend_from_slice(self.trailer);
+        Ok(bytecode)
+    }
+
+    // Maps every original offset to the offset of the same instruction after patching, given
+    // the sizes of the rewritten pushes.
+    fn relocate(&self, widths: &HashMap<Hex, usize>) -> HashMap<Hex, Hex> {
+        let mut offsets = HashMap::new();
+        let mut new_offset = 0;
+        for (offset, instruction) in &self.instructions {
+            new_offset += self.insertions.get(offset).map_or(0, Vec::len);
+            offsets.insert(*offset, Hex::from(new_offset));
+            new_offset += match (self.replacements.get(offset), widths.get(offset)) {
+                (Some(code), _) => code.len(),
+                (None, Some(width)) => 1 + width,
+                (None, None) => 1 + instruction.args.len(),
+            };
+        }
+        offsets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assembler::assemble,
+        flow_parser::FlowParser,
+        hex::Hex,
+        opcode::Hardfork,
+        parser::Parser,
+        testing::{parse, parse_with_metadata, SOLC_METADATA},
+    };
+
+    use super::{Patch, PatchError};
+
+    const PROGRAM: &str = "
+        PUSH1 0x00
+        CALLDATALOAD
+        PUSH target
+        JUMPI
+        STOP
+        target: JUMPDEST
+        PUSH1 0x01
+        STOP
+        ";
+
+    #[test]
+    fn insert_relocates_jumps() {
+        let parser = parse(PROGRAM);
+        let mut flow_parser = FlowParser::new(parser.get_instructions(), parser.get_bytecode());
+        flow_parser.parse_flows();
+        let mut patch = Patch::new(&parser, flow_parser.flows()).unwrap();
+        let probe = assemble("PUSH1 0x2a\nPOP").unwrap();
+        patch.insert(Hex::new(0), &probe).unwrap();
+        // Right after the JUMPDEST at 0x07.
+        patch.insert(Hex::new(8), &probe).unwrap();
+        assert_eq!(
+            patch.apply().unwrap(),
+            assemble(
+                "
+                PUSH1 0x2a
+                POP
+                PUSH1 0x00
+                CALLDATALOAD
+                PUSH target
+                JUMPI
+                STOP
+                target: JUMPDEST
+                PUSH1 0x2a
+                POP
+                PUSH1 0x01
+                STOP
+                "
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn insert_widens_pushes() {
+        let parser = parse(PROGRAM);
+        let mut flow_parser = FlowParser::new(parser.get_instructions(), parser.get_bytecode());
+        flow_parser.parse_flows();
+        let mut patch = Patch::new(&parser, flow_parser.flows()).unwrap();
+        patch.insert(Hex::new(2), &[0x5b; 300]).unwrap();
+        // PUSH1 0x00, the inserted code and CALLDATALOAD move the PUSH of the target to 0x12f
+        // and the target itself to 0x134.
+        let source = PROGRAM.replacen("CALLDATALOAD", &"JUMPDEST\n".repeat(300), 1);
+        let source = source.replacen("PUSH target", "CALLDATALOAD\nPUSH target", 1);
+        let expected = assemble(&source).unwrap();
+        let patched = patch.apply().unwrap();
+        assert_eq!(patched[0x12f..0x132], [0x61, 0x01, 0x34]);
+        assert_eq!(patched, expected);
+    }
+
+    #[test]
+    fn remove_and_replace() {
+        let parser = parse(PROGRAM);
+        let mut flow_parser = FlowParser::new(parser.get_instructions(), parser.get_bytecode());
+        flow_parser.parse_flows();
+        let mut patch = Patch::new(&parser, flow_parser.flows()).unwrap();
+        assert_eq!(
+            patch.remove(Hex::new(7)),
+            Err(PatchError::RemovedJumpTarget(Hex::new(7)))
+        );
+        assert_eq!(
+            patch.insert(Hex::new(1), &[]),
+            Err(PatchError::NotAnInstruction(Hex::new(1)))
+        );
+        patch.remove(Hex::new(6)).unwrap();
+        patch.replace(Hex::new(8), &[0x5f]).unwrap();
+        assert_eq!(
+            patch.replace(Hex::new(8), &[]),
+            Err(PatchError::AlreadyReplaced(Hex::new(8)))
+        );
+        assert_eq!(
+            patch.apply().unwrap(),
+            assemble(
+                "
+                PUSH1 0x00
+                CALLDATALOAD
+                PUSH target
+                JUMPI
+                target: JUMPDEST
+                PUSH0
+                STOP
+                "
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn keeps_metadata() {
+        let parser = parse_with_metadata("PUSH1 0x03\nJUMP\nJUMPDEST\nSTOP", &SOLC_METADATA);
+        let mut flow_parser = FlowParser::new(parser.get_instructions(), parser.get_bytecode());
+        flow_parser.parse_flows();
+        let mut patch = Patch::new(&parser, flow_parser.flows()).unwrap();
+        patch.insert(Hex::new(0), &[0x5b]).unwrap();
+        let patched = patch.apply().unwrap();
+        assert_eq!(patched[..6], [0x5b, 0x60, 0x04, 0x56, 0x5b, 0x00]);
+        assert_eq!(patched[6..], SOLC_METADATA);
+    }
+
+    #[test]
+    fn eof_is_not_patched() {
+        // One code section holding PUSH0 POP STOP.
+        let bytecode: [u8; 22] = [
+            0xef, 0x00, 0x01, 0x01, 0x00, 0x04, 0x02, 0x00, 0x01, 0x00, 0x03, 0x04, 0x00, 0x00,
+            0x00, 0x00, 0x80, 0x00, 0x01, 0x5f, 0x50, 0x00,
+        ];
+        let parser = Parser::from_bytes(&bytecode, Hardfork::Prague).unwrap();
+        assert!(parser.get_container().is_some());
+        assert_eq!(
+            Patch::new(&parser, &[]).err(),
+            Some(PatchError::EofContainer)
+        );
+    }
+}
@@ -1,17 +1,43 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 
 use crate::hex::Hex;
 
+// Describes what a value really stands for when it could not be computed concretely. The value
+// of such an element is only a best-effort stand-in.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Symbol {
+    // keccak256 over memory[start..end], of which some bytes were symbolic themselves.
+    MemoryHash { start: Hex, end: Hex },
+    // A value from the environment of the transaction, such as CALLER, that was not provided.
+    UnknownEnvironment(&'static str),
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Symbol::MemoryHash { start, end } => {
+                write!(f, "keccak256(memory[{:#x}..{:#x}])", start, end)
+            }
+            Symbol::UnknownEnvironment(name) => write!(f, "unknown environment value {}", name),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub struct StackElement {
     pub value: Hex,
     pub origin: Hex,
     pub size: usize,
+    pub symbol: Option<Symbol>,
 }
 
 impl Debug for StackElement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:04x}", self.value)
+        if let Some(symbol) = &self.symbol {
+            write!(f, "{}", symbol)
+        } else {
+            write!(f, "{:04x}", self.value)
+        }
     }
 }
 
@@ -35,10 +61,6 @@ impl Stack {
         self.elements.push(element);
     }
 
-    pub fn extend(&mut self, elements: Stack) {
-        self.elements.extend(elements.elements);
-    }
-
     pub fn get(&self, index: usize) -> Option<&StackElement> {
         self.elements.get(index)
     }
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use crate::{hex::Hex, stack::StackElement};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageElement {
+    pub value: Hex,
+    // Where the stored value came from, or None when the value was part of the pre-state or was
+    // never written.
+    pub origin: Option<Hex>,
+}
+
+// Contract storage. Unlike memory, storage is a sparse map of 256-bit slots where every slot
+// that was never written reads as zero. Besides the persistent slots there are transient slots
+// (EIP-1153), which work the same but only live for the duration of a single transaction, so
+// they never have a pre-state.
+#[derive(Debug, Clone, Default)]
+pub struct Storage {
+    slots: HashMap<Hex, StorageElement>,
+    transient: HashMap<Hex, StorageElement>,
+}
+
+impl Storage {
+    pub fn new() -> Storage {
+        Storage {
+            slots: HashMap::new(),
+            transient: HashMap::new(),
+        }
+    }
+
+    // Creates storage from a known pre-state, for example the state of a deployed contract.
+    pub fn from_slots(slots: impl IntoIterator<Item = (Hex, Hex)>) -> Storage {
+        Storage {
+            slots: slots
+                .into_iter()
+                .map(|(key, value)| {
+                    (
+                        key,
+                        StorageElement {
+                            value,
+                            origin: None,
+                        },
+                    )
+                })
+                .collect(),
+            transient: HashMap::new(),
+        }
+    }
+
+    pub fn sload(&self, key: Hex) -> StorageElement {
+        load(&self.slots, key)
+    }
+
+    pub fn sstore(&mut self, key: Hex, element: StackElement) {
+        store(&mut self.slots, key, element);
+    }
+
+    pub fn tload(&self, key: Hex) -> StorageElement {
+        load(&self.transient, key)
+    }
+
+    pub fn tstore(&mut self, key: Hex, element: StackElement) {
+        store(&mut self.transient, key, element);
+    }
+}
+
+fn load(slots: &HashMap<Hex, StorageElement>, key: Hex) -> StorageElement {
+    slots.get(&key).cloned().unwrap_or(StorageElement {
+        value: Hex::new(0),
+        origin: None,
+    })
+}
+
+fn store(slots: &mut HashMap<Hex, StorageElement>, key: Hex, element: StackElement) {
+    slots.insert(
+        key,
+        StorageElement {
+            value: element.value,
+            origin: Some(element.origin),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{hex::Hex, stack::StackElement};
+
+    use super::{Storage, StorageElement};
+
+    #[test]
+    fn unknown_slot_reads_zero() {
+        let storage = Storage::new();
+        assert_eq!(
+            storage.sload(Hex::MAX),
+            StorageElement {
+                value: Hex::new(0),
+                origin: None
+            }
+        );
+    }
+
+    #[test]
+    fn store_and_load() {
+        let mut storage = Storage::new();
+        let key = Hex::ONE << 255;
+        storage.sstore(
+            key,
+            StackElement {
+                value: Hex::new(0x1234),
+                origin: Hex::new(1),
+                size: 2,
+                symbol: None,
+            },
+        );
+        assert_eq!(
+            storage.sload(key),
+            StorageElement {
+                value: Hex::new(0x1234),
+                origin: Some(Hex::new(1))
+            }
+        );
+        assert_eq!(storage.sload(Hex::new(0)).value, Hex::new(0));
+    }
+
+    #[test]
+    fn transient_slots_are_separate() {
+        let mut storage = Storage::from_slots([(Hex::new(0), Hex::new(0xff))]);
+        storage.tstore(
+            Hex::new(1),
+            StackElement {
+                value: Hex::new(0xee),
+                origin: Hex::new(4),
+                size: 1,
+                symbol: None,
+            },
+        );
+        assert_eq!(storage.tload(Hex::new(0)).value, Hex::new(0));
+        assert_eq!(storage.tload(Hex::new(1)).value, Hex::new(0xee));
+        assert_eq!(storage.sload(Hex::new(1)).value, Hex::new(0));
+    }
+
+    #[test]
+    fn pre_state() {
+        let storage = Storage::from_slots([(Hex::new(0), Hex::new(0xff))]);
+        assert_eq!(
+            storage.sload(Hex::new(0)),
+            StorageElement {
+                value: Hex::new(0xff),
+                origin: None
+            }
+        );
+    }
+}
@@ -0,0 +1,19 @@
+// Fixtures the tests of several modules share.
+use crate::{assembler::assemble, opcode::Hardfork, parser::Parser};
+
+// The metadata solc 0.8.19 appends when it leaves out the source hash, {"solc": h'000813'},
+// followed by its length.
+pub const SOLC_METADATA: [u8; 12] = [
+    0xa1, 0x64, 0x73, 0x6f, 0x6c, 0x63, 0x43, 0x00, 0x08, 0x13, 0x00, 0x0a,
+];
+
+// Assembles `source` and disassembles it as Cancun code.
+pub fn parse(source: &str) -> Parser {
+    parse_with_metadata(source, &[])
+}
+
+// Assembles `source`, appends `metadata` and disassembles the result as Cancun code.
+pub fn parse_with_metadata(source: &str, metadata: &[u8]) -> Parser {
+    let bytecode = [assemble(source).unwrap(), metadata.to_vec()].concat();
+    Parser::from_bytes(&bytecode, Hardfork::Cancun).unwrap()
+}